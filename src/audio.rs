@@ -0,0 +1,228 @@
+use bevy::{
+    audio::{DefaultSpatialScale, SpatialScale, Volume},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    level::{CurrentLevel, Interact, Interactable, Level, TILE_SIZE},
+    loading::AudioAssets,
+    movement::{GridPosition, MoveBlocked, TurnTaken},
+    settings::Settings,
+    GameState,
+};
+
+pub struct InternalAudioPlugin;
+
+/// This plugin is responsible to control the game audio
+/// Sound effects are spatial, so they are heard from where they happen
+impl Plugin for InternalAudioPlugin {
+    fn build(&self, app: &mut App) {
+        /* One tile is one unit for the panning and fall off of spatial audio */
+        app.insert_resource(DefaultSpatialScale(SpatialScale::new(1.0 / TILE_SIZE)))
+            .add_systems(
+                Update,
+                (
+                    play_footsteps,
+                    play_bumps,
+                    play_interactions,
+                    start_emitters,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, (play_ambience, apply_volumes));
+    }
+}
+
+/// Looping positional sound, e.g. a crackling torch or running water
+#[derive(Debug, Clone, Copy, Component, Deserialize)]
+pub enum EmitterSound {
+    Torch,
+    Water,
+}
+
+impl EmitterSound {
+    fn source(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            EmitterSound::Torch => audio_assets.torch.clone(),
+            EmitterSound::Water => audio_assets.water.clone(),
+        }
+    }
+}
+
+/// A positional sound placed in a level file
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SoundEmitter {
+    pub grid_position: GridPosition,
+    pub sound: EmitterSound,
+}
+
+impl SoundEmitter {
+    pub fn bundle(&self) -> (SpatialBundle, EmitterSound) {
+        (
+            SpatialBundle::from_transform(Transform::from_translation(
+                (&self.grid_position).into(),
+            )),
+            self.sound,
+        )
+    }
+}
+
+/// The ambient loop of the current level
+#[derive(Component)]
+struct Ambience;
+
+/// Plays `source` once at `translation`
+fn play_at(
+    commands: &mut Commands,
+    source: Handle<AudioSource>,
+    translation: Vec3,
+    settings: &Settings,
+) {
+    commands.spawn((
+        AudioBundle {
+            source,
+            settings: PlaybackSettings::DESPAWN
+                .with_spatial(true)
+                .with_volume(Volume::new(settings.effects_volume)),
+        },
+        SpatialBundle::from_transform(Transform::from_translation(translation)),
+    ));
+}
+
+/* Turns also end in a TurnTaken, but only moves change the position */
+fn play_footsteps(
+    mut commands: Commands,
+    mut turn_taken_evr: EventReader<TurnTaken>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    grid_positions: Query<Ref<GridPosition>>,
+) {
+    for event in turn_taken_evr.read() {
+        let Ok(grid_position) = grid_positions.get(event.entity) else {
+            continue;
+        };
+        if grid_position.is_changed() {
+            play_at(
+                &mut commands,
+                audio_assets.footstep.clone(),
+                grid_position.as_ref().into(),
+                &settings,
+            );
+        }
+    }
+}
+
+fn play_bumps(
+    mut commands: Commands,
+    mut move_blocked_evr: EventReader<MoveBlocked>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    grid_positions: Query<&GridPosition>,
+) {
+    for event in move_blocked_evr.read() {
+        if let Ok(grid_position) = grid_positions.get(event.entity) {
+            play_at(
+                &mut commands,
+                audio_assets.bump.clone(),
+                grid_position.into(),
+                &settings,
+            );
+        }
+    }
+}
+
+fn play_interactions(
+    mut commands: Commands,
+    mut interact_evr: EventReader<Interact>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    grid_positions: Query<&GridPosition>,
+) {
+    for event in interact_evr.read() {
+        let Ok(grid_position) = grid_positions.get(event.source) else {
+            continue;
+        };
+        let source = match event.target {
+            Interactable::Chest(_) => audio_assets.chest.clone(),
+            Interactable::Door(_) => audio_assets.door.clone(),
+            Interactable::Npc(_) => continue,
+            Interactable::Readable(_) => audio_assets.page.clone(),
+            Interactable::Switch(_) => audio_assets.lever.clone(),
+            Interactable::Teleporter(_) => audio_assets.teleporter.clone(),
+        };
+        play_at(&mut commands, source, grid_position.into(), &settings);
+    }
+}
+
+fn start_emitters(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    emitters: Query<(Entity, &EmitterSound), Added<EmitterSound>>,
+) {
+    for (entity, emitter_sound) in &emitters {
+        commands.entity(entity).insert(AudioBundle {
+            source: emitter_sound.source(&audio_assets),
+            settings: PlaybackSettings::LOOP
+                .with_spatial(true)
+                .with_volume(Volume::new(settings.effects_volume)),
+        });
+    }
+}
+
+/// Switches the ambient loop with the level, it stops when the level is left
+fn play_ambience(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    audio_assets: Option<Res<AudioAssets>>,
+    settings: Res<Settings>,
+    ambiences: Query<Entity, With<Ambience>>,
+) {
+    if !current_level.is_changed() {
+        return;
+    }
+
+    for entity in &ambiences {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (Some(level), Some(audio_assets)) = (level_assets.get(&current_level.0), audio_assets)
+    else {
+        return;
+    };
+    let Some(path) = &level.ambience else {
+        return;
+    };
+    let Some(source) = audio_assets.ambience.get(path) else {
+        warn!("Unknown ambience {path}");
+        return;
+    };
+
+    commands.spawn((
+        AudioBundle {
+            source: source.clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(settings.music_volume)),
+        },
+        Ambience,
+    ));
+}
+
+/* The global volume only applies when a sound starts, so playing loops are updated here */
+fn apply_volumes(
+    settings: Res<Settings>,
+    ambiences: Query<&AudioSink, With<Ambience>>,
+    emitters: Query<&SpatialAudioSink, With<EmitterSound>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for sink in &ambiences {
+        sink.set_volume(settings.master_volume * settings.music_volume);
+    }
+    for sink in &emitters {
+        sink.set_volume(settings.master_volume * settings.effects_volume);
+    }
+}
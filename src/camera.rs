@@ -0,0 +1,185 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    movement::{EaseSettings, EaseTo, GridDirection, GridPosition, MoveBlocked},
+    settings::Settings,
+    GameState,
+};
+
+pub struct CameraEffectsPlugin;
+
+/// This plugin moves cameras with [`CameraEffects`] relative to their parent to make movement feel physical
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScreenShake>().add_systems(
+            Update,
+            (
+                start_step_effects,
+                start_turn_effects,
+                shake_on_move_blocked,
+                add_trauma,
+                apply_camera_effects,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Adds trauma to all [`CameraEffects`], e.g. when taking damage or triggering a trap
+#[derive(Debug, Event)]
+pub struct ScreenShake {
+    /// Between 0 and 1, the shake grows with the square of the accumulated trauma
+    pub trauma: f32,
+}
+
+/// Head-bob while moving, a lean while turning and trauma based shake
+#[derive(Debug, Default, Component)]
+pub struct CameraEffects {
+    step: Option<Timer>,
+    /// Timer and direction of the lean, positive leans right
+    turn: Option<(Timer, f32)>,
+    trauma: f32,
+}
+
+const BOB_HEIGHT: f32 = 1.5;
+const TURN_ROLL: f32 = 0.04;
+const MAX_SHAKE_OFFSET: f32 = 2.0;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+const BUMP_TRAUMA: f32 = 0.3;
+
+fn start_step_effects(
+    default_ease_settings: Res<EaseSettings>,
+    movers: Query<(&Children, Option<&EaseSettings>), Added<EaseTo<GridPosition>>>,
+    mut effects: Query<&mut CameraEffects>,
+) {
+    for (children, ease_settings) in &movers {
+        let ease_settings = ease_settings.unwrap_or(&default_ease_settings);
+        if ease_settings.is_snap() {
+            continue;
+        }
+
+        let mut iter = effects.iter_many_mut(children);
+        while let Some(mut camera_effects) = iter.fetch_next() {
+            camera_effects.step = Some(Timer::new(ease_settings.duration, TimerMode::Once));
+        }
+    }
+}
+
+fn start_turn_effects(
+    default_ease_settings: Res<EaseSettings>,
+    turners: Query<
+        (
+            &Children,
+            &GridDirection,
+            &EaseTo<GridDirection>,
+            Option<&EaseSettings>,
+        ),
+        Added<EaseTo<GridDirection>>,
+    >,
+    mut effects: Query<&mut CameraEffects>,
+) {
+    for (children, direction, ease_to, ease_settings) in &turners {
+        let ease_settings = ease_settings.unwrap_or(&default_ease_settings);
+        if ease_settings.is_snap() {
+            continue;
+        }
+
+        /* Turning around does not lean */
+        let lean = if ease_to.target == direction.right() {
+            1.0
+        } else if ease_to.target == direction.left() {
+            -1.0
+        } else {
+            continue;
+        };
+
+        let mut iter = effects.iter_many_mut(children);
+        while let Some(mut camera_effects) = iter.fetch_next() {
+            camera_effects.turn = Some((Timer::new(ease_settings.duration, TimerMode::Once), lean));
+        }
+    }
+}
+
+fn shake_on_move_blocked(
+    mut move_blocked_evr: EventReader<MoveBlocked>,
+    children: Query<&Children>,
+    mut effects: Query<&mut CameraEffects>,
+) {
+    for event in move_blocked_evr.read() {
+        let Ok(children) = children.get(event.entity) else {
+            continue;
+        };
+        let mut iter = effects.iter_many_mut(children);
+        while let Some(mut camera_effects) = iter.fetch_next() {
+            camera_effects.trauma = (camera_effects.trauma + BUMP_TRAUMA).min(1.0);
+        }
+    }
+}
+
+fn add_trauma(
+    mut screen_shake_evr: EventReader<ScreenShake>,
+    mut effects: Query<&mut CameraEffects>,
+) {
+    for event in screen_shake_evr.read() {
+        for mut camera_effects in &mut effects {
+            camera_effects.trauma = (camera_effects.trauma + event.trauma).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/* The camera is a child, so its transform only holds the offset from the player */
+fn apply_camera_effects(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut cameras: Query<(&mut Transform, &mut CameraEffects)>,
+) {
+    for (mut transform, mut camera_effects) in &mut cameras {
+        if !settings.camera_effects {
+            *camera_effects = CameraEffects::default();
+            *transform = Transform::IDENTITY;
+            continue;
+        }
+
+        let mut translation = Vec3::ZERO;
+        let mut roll = 0.0;
+        let mut pitch = 0.0;
+        let mut yaw = 0.0;
+
+        if let Some(step) = camera_effects.step.as_mut() {
+            step.tick(time.delta());
+            translation.y -= BOB_HEIGHT * (step.fraction() * PI).sin();
+            if step.finished() {
+                camera_effects.step = None;
+            }
+        }
+
+        if let Some((turn, lean)) = camera_effects.turn.as_mut() {
+            turn.tick(time.delta());
+            roll -= *lean * TURN_ROLL * (turn.fraction() * PI).sin();
+            if turn.finished() {
+                camera_effects.turn = None;
+            }
+        }
+
+        if camera_effects.trauma > 0.0 {
+            /* Overlapping sines are cheap noise, good enough for shaking */
+            let shake = camera_effects.trauma.powi(2);
+            let t = time.elapsed_seconds() * 40.0;
+            translation.x += MAX_SHAKE_OFFSET * shake * (t * 1.1).sin();
+            translation.y += MAX_SHAKE_OFFSET * shake * (t * 1.7 + 1.0).sin();
+            roll += MAX_SHAKE_ANGLE * shake * (t * 0.9 + 2.0).sin();
+            pitch += MAX_SHAKE_ANGLE * shake * (t * 1.3 + 3.0).sin();
+            yaw += MAX_SHAKE_ANGLE * shake * (t * 1.5 + 4.0).sin();
+            camera_effects.trauma =
+                (camera_effects.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+        }
+
+        transform.translation = translation;
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+    }
+}
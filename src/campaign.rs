@@ -0,0 +1,270 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::HashSet,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    level::{CurrentLevel, Level, Player},
+    loading::CampaignAssets,
+    message_log::{LogMessage, MessageCategory},
+    movement::GridPosition,
+    settings::{load_config, save_config},
+    toast::Toast,
+    GameState,
+};
+
+pub struct CampaignPlugin;
+
+/// This plugin loads campaigns, which define the order of levels, where to start and how to win
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Campaign>()
+            .init_asset_loader::<CampaignAssetLoader>()
+            .init_resource::<ActiveCampaign>()
+            .insert_resource(CampaignProgress::load())
+            .add_systems(OnExit(GameState::Loading), select_default_campaign)
+            .add_systems(
+                Update,
+                (announce_level, track_progress, check_victory)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Deserialize, Asset, TypePath)]
+pub struct Campaign {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Path of the level the campaign starts in
+    pub entry: String,
+    pub levels: Vec<CampaignLevel>,
+    /// The campaign is won, as soon as any of the conditions is met
+    #[serde(default)]
+    pub victory: Vec<VictoryCondition>,
+}
+
+impl Campaign {
+    pub fn level(&self, path: &str) -> Option<&CampaignLevel> {
+        self.levels.iter().find(|level| level.path == path)
+    }
+
+    fn level_index(&self, path: &str) -> Option<usize> {
+        self.levels.iter().position(|level| level.path == path)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CampaignLevel {
+    /// Path of the level file, e.g. `level/000.lvl`
+    pub path: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum VictoryCondition {
+    /// Enter the level with the path
+    ReachLevel(String),
+    /// Step on the position in the level with the path
+    ReachPosition {
+        level: String,
+        position: GridPosition,
+    },
+}
+
+impl VictoryCondition {
+    fn is_met(&self, level_path: &str, grid_position: &GridPosition) -> bool {
+        match self {
+            VictoryCondition::ReachLevel(level) => level == level_path,
+            VictoryCondition::ReachPosition { level, position } => {
+                level == level_path && position == grid_position
+            }
+        }
+    }
+}
+
+/// Holds a Handle to the Campaign that is played
+#[derive(Debug, Default, Resource, Deref)]
+pub struct ActiveCampaign(pub Handle<Campaign>);
+
+/// Levels and campaigns the player has completed, by their paths, kept next to the settings
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignProgress {
+    completed_levels: HashSet<String>,
+    completed_campaigns: HashSet<String>,
+}
+
+impl CampaignProgress {
+    const FILE_NAME: &'static str = "progress.ron";
+
+    pub fn is_level_completed(&self, path: &str) -> bool {
+        self.completed_levels.contains(path)
+    }
+
+    pub fn is_campaign_completed(&self, campaign: &Handle<Campaign>) -> bool {
+        campaign
+            .path()
+            .is_some_and(|path| self.completed_campaigns.contains(&path.to_string()))
+    }
+
+    /// Starts without progress, if there is none or it is broken
+    fn load() -> Self {
+        load_config(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(error) = save_config(Self::FILE_NAME, self) {
+            warn!("Could not save the campaign progress: {error}");
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum CampaignAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+    /// The entry level has to be one of the levels of the campaign
+    #[error("The entry level {0} is not part of the campaign")]
+    UnknownEntry(String),
+}
+
+#[derive(Default)]
+pub struct CampaignAssetLoader;
+
+impl AssetLoader for CampaignAssetLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = CampaignAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let custom_asset = ron::de::from_bytes::<Campaign>(&bytes)?;
+        if custom_asset.level(&custom_asset.entry).is_none() {
+            return Err(CampaignAssetLoaderError::UnknownEntry(custom_asset.entry));
+        }
+        Ok(custom_asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign"]
+    }
+}
+
+/// Path of a level handle in the form used by [`Campaign`]s
+pub fn level_path(handle: &Handle<Level>) -> Option<String> {
+    handle.path().map(ToString::to_string)
+}
+
+fn select_default_campaign(
+    mut active_campaign: ResMut<ActiveCampaign>,
+    campaign_assets: Res<CampaignAssets>,
+) {
+    let mut campaigns = campaign_assets.campaigns.iter().collect::<Vec<_>>();
+    campaigns.sort_by_key(|(path, _)| *path);
+
+    if let Some((path, campaign)) = campaigns.first() {
+        info!("Selecting campaign {path}");
+        active_campaign.0 = (*campaign).clone();
+    }
+}
+
+fn announce_level(
+    current_level: Res<CurrentLevel>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    if !current_level.is_changed() {
+        return;
+    }
+
+    let Some(campaign) = campaigns.get(&active_campaign.0) else {
+        return;
+    };
+    if let Some(level) = level_path(&current_level.0).and_then(|path| campaign.level(&path)) {
+        log_message_evw.send(LogMessage::new(
+            MessageCategory::Level,
+            format!("Entering {}: {}", level.title, level.description),
+        ));
+    }
+}
+
+/// A level is completed, when the player advances to a later level of the campaign
+fn track_progress(
+    current_level: Res<CurrentLevel>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+    mut progress: ResMut<CampaignProgress>,
+    mut previous_level: Local<Option<String>>,
+) {
+    if !current_level.is_changed() {
+        return;
+    }
+
+    let path = level_path(&current_level.0);
+    if let (Some(campaign), Some(previous), Some(current)) = (
+        campaigns.get(&active_campaign.0),
+        previous_level.as_deref(),
+        path.as_deref(),
+    ) {
+        if campaign.level_index(current) > campaign.level_index(previous)
+            && progress.completed_levels.insert(previous.to_string())
+        {
+            progress.save();
+        }
+    }
+    *previous_level = path;
+}
+
+fn check_victory(
+    current_level: Res<CurrentLevel>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    mut progress: ResMut<CampaignProgress>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toast_evw: EventWriter<Toast>,
+) {
+    let Some(campaign) = campaigns.get(&active_campaign.0) else {
+        return;
+    };
+    let Some(path) = level_path(&current_level.0) else {
+        return;
+    };
+
+    for grid_position in &players {
+        if campaign
+            .victory
+            .iter()
+            .any(|condition| condition.is_met(&path, grid_position))
+        {
+            info!("Won campaign {}", campaign.title);
+            progress.completed_levels.insert(path.clone());
+            if let Some(campaign_path) = active_campaign.path() {
+                progress
+                    .completed_campaigns
+                    .insert(campaign_path.to_string());
+            }
+            progress.save();
+            toast_evw.send(Toast(format!("Victory! {} is completed", campaign.title)));
+            next_state.set(GameState::Menu);
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use std::{cmp::Ordering, iter::Peekable, str::CharIndices};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    flags::{FlagValue, GameFlags},
+    inventory::Inventory,
+    item::ItemId,
+};
+
+/// A requirement written as an expression in assets, e.g. `HasItem("key") && !Flag("gate_open")`
+///
+/// `HasItem("coin", 3)` requires at least three of an item
+/// Flags can also be compared, e.g. `Flag("coins") >= 3` or `Flag("mood") == "angry"`,
+/// `!` binds tighter than `&&`, which binds tighter than `||`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Condition {
+    HasItem(ItemId, u32),
+    /// Whether the flag is set to something other than `false`, `0` or `""`
+    Flag(String),
+    /// Unset flags compare as `false`, `0` or `""`, depending on the other side
+    Compare(String, Comparison, FlagValue),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{operator}")
+    }
+}
+
+impl Condition {
+    /// Checks the condition for the entity with the inventory, e.g. the player
+    pub fn is_met(&self, flags: &GameFlags, inventory: Option<&Inventory>) -> bool {
+        match self {
+            Condition::HasItem(item, count) => {
+                inventory.is_some_and(|inventory| inventory.count(item) >= *count)
+            }
+            Condition::Flag(flag) => flags.is_set(flag),
+            Condition::Compare(flag, comparison, expected) => {
+                let ordering = match (flags.get(flag), expected) {
+                    (Some(FlagValue::Int(value)), FlagValue::Int(expected)) => value.cmp(expected),
+                    (None, FlagValue::Int(expected)) => 0.cmp(expected),
+                    (Some(FlagValue::Bool(value)), FlagValue::Bool(expected)) => {
+                        value.cmp(expected)
+                    }
+                    (None, FlagValue::Bool(expected)) => false.cmp(expected),
+                    (Some(FlagValue::Text(value)), FlagValue::Text(expected)) => {
+                        value.cmp(expected)
+                    }
+                    (None, FlagValue::Text(expected)) => "".cmp(expected.as_str()),
+                    /* Values of different types are never equal */
+                    _ => return *comparison == Comparison::NotEqual,
+                };
+                comparison.matches(ordering)
+            }
+            Condition::Not(condition) => !condition.is_met(flags, inventory),
+            Condition::And(left, right) => {
+                left.is_met(flags, inventory) && right.is_met(flags, inventory)
+            }
+            Condition::Or(left, right) => {
+                left.is_met(flags, inventory) || right.is_met(flags, inventory)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConditionParseError {
+    #[error("The condition ends early")]
+    UnexpectedEnd,
+    #[error("The text starting at {0} is not closed")]
+    UnterminatedText(usize),
+    #[error("Unexpected \"{found}\" at {position}")]
+    Unexpected { found: String, position: usize },
+    #[error("There is no condition named \"{0}\"")]
+    UnknownCondition(String),
+}
+
+impl TryFrom<String> for Condition {
+    type Error = ConditionParseError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        source.parse()
+    }
+}
+
+impl std::str::FromStr for Condition {
+    type Err = ConditionParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?.into_iter().peekable(),
+        };
+        let condition = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(condition),
+            Some((position, token)) => Err(ConditionParseError::Unexpected {
+                found: token.to_string(),
+                position,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Int(i64),
+    Open,
+    Close,
+    Comma,
+    And,
+    Or,
+    Not,
+    Compare(Comparison),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "{ident}"),
+            Token::Text(text) => write!(f, "\"{text}\""),
+            Token::Int(value) => write!(f, "{value}"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::Compare(comparison) => write!(f, "{comparison}"),
+        }
+    }
+}
+
+/// Splits the source into tokens along with their byte position
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ConditionParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '&' if next_is(&mut chars, '&') => Token::And,
+            '|' if next_is(&mut chars, '|') => Token::Or,
+            '=' if next_is(&mut chars, '=') => Token::Compare(Comparison::Equal),
+            '!' if next_is(&mut chars, '=') => Token::Compare(Comparison::NotEqual),
+            '!' => Token::Not,
+            '<' if next_is(&mut chars, '=') => Token::Compare(Comparison::LessOrEqual),
+            '<' => Token::Compare(Comparison::Less),
+            '>' if next_is(&mut chars, '=') => Token::Compare(Comparison::GreaterOrEqual),
+            '>' => Token::Compare(Comparison::Greater),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err(ConditionParseError::UnterminatedText(position)),
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => return Err(ConditionParseError::UnterminatedText(position)),
+                    }
+                }
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = c.to_string();
+                while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    number.push(digit);
+                }
+                Token::Int(
+                    number
+                        .parse()
+                        .map_err(|_| ConditionParseError::Unexpected {
+                            found: number,
+                            position,
+                        })?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            c => {
+                return Err(ConditionParseError::Unexpected {
+                    found: c.to_string(),
+                    position,
+                })
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+fn next_is(chars: &mut Peekable<CharIndices>, expected: char) -> bool {
+    chars.next_if(|(_, c)| *c == expected).is_some()
+}
+
+/// Recursive descent over the tokens, one method per precedence level
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<(usize, Token)>>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<(usize, Token), ConditionParseError> {
+        self.tokens.next().ok_or(ConditionParseError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ConditionParseError> {
+        match self.next()? {
+            (_, token) if token == expected => Ok(()),
+            (position, token) => Err(ConditionParseError::Unexpected {
+                found: token.to_string(),
+                position,
+            }),
+        }
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        self.tokens
+            .next_if(|(_, token)| token == expected)
+            .is_some()
+    }
+
+    fn or(&mut self) -> Result<Condition, ConditionParseError> {
+        let mut condition = self.and()?;
+        while self.eat(&Token::Or) {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, ConditionParseError> {
+        let mut condition = self.not()?;
+        while self.eat(&Token::And) {
+            condition = Condition::And(Box::new(condition), Box::new(self.not()?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self) -> Result<Condition, ConditionParseError> {
+        if self.eat(&Token::Not) {
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Condition, ConditionParseError> {
+        match self.next()? {
+            (_, Token::Open) => {
+                let condition = self.or()?;
+                self.expect(Token::Close)?;
+                Ok(condition)
+            }
+            (_, Token::Ident(name)) if name == "HasItem" => {
+                self.expect(Token::Open)?;
+                let item = match self.next()? {
+                    (_, Token::Text(item)) => ItemId(item),
+                    (position, token) => {
+                        return Err(ConditionParseError::Unexpected {
+                            found: token.to_string(),
+                            position,
+                        })
+                    }
+                };
+                let count = match self.eat(&Token::Comma) {
+                    true => match self.next()? {
+                        (position, Token::Int(count)) => {
+                            u32::try_from(count).map_err(|_| ConditionParseError::Unexpected {
+                                found: count.to_string(),
+                                position,
+                            })?
+                        }
+                        (position, token) => {
+                            return Err(ConditionParseError::Unexpected {
+                                found: token.to_string(),
+                                position,
+                            })
+                        }
+                    },
+                    false => 1,
+                };
+                self.expect(Token::Close)?;
+                Ok(Condition::HasItem(item, count))
+            }
+            (_, Token::Ident(name)) if name == "Flag" => {
+                self.expect(Token::Open)?;
+                let flag = match self.next()? {
+                    (_, Token::Text(flag)) => flag,
+                    (position, token) => {
+                        return Err(ConditionParseError::Unexpected {
+                            found: token.to_string(),
+                            position,
+                        })
+                    }
+                };
+                self.expect(Token::Close)?;
+                let Some(&(_, Token::Compare(comparison))) = self.tokens.peek() else {
+                    return Ok(Condition::Flag(flag));
+                };
+                self.tokens.next();
+                let value = match self.next()? {
+                    (_, Token::Int(value)) => FlagValue::Int(value),
+                    (_, Token::Text(value)) => FlagValue::Text(value),
+                    (_, Token::Ident(value)) if value == "true" => FlagValue::Bool(true),
+                    (_, Token::Ident(value)) if value == "false" => FlagValue::Bool(false),
+                    (position, token) => {
+                        return Err(ConditionParseError::Unexpected {
+                            found: token.to_string(),
+                            position,
+                        })
+                    }
+                };
+                Ok(Condition::Compare(flag, comparison, value))
+            }
+            (_, Token::Ident(name)) => Err(ConditionParseError::UnknownCondition(name)),
+            (position, token) => Err(ConditionParseError::Unexpected {
+                found: token.to_string(),
+                position,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Condition, ConditionParseError> {
+        source.parse()
+    }
+
+    fn flag(name: &str) -> Box<Condition> {
+        Box::new(Condition::Flag(name.to_string()))
+    }
+
+    fn flags(values: &[(&str, FlagValue)]) -> GameFlags {
+        let mut flags = GameFlags::default();
+        for (flag, value) in values {
+            flags.set(*flag, value.clone());
+        }
+        flags
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        assert_eq!(
+            parse(r#"!Flag("a") && Flag("b") || Flag("c")"#),
+            Ok(Condition::Or(
+                Box::new(Condition::And(
+                    Box::new(Condition::Not(flag("a"))),
+                    flag("b")
+                )),
+                flag("c"),
+            ))
+        );
+        assert_eq!(
+            parse(r#"Flag("a") || Flag("b") && Flag("c")"#),
+            Ok(Condition::Or(
+                flag("a"),
+                Box::new(Condition::And(flag("b"), flag("c"))),
+            ))
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            parse(r#"!(Flag("a") || Flag("b")) && Flag("c")"#),
+            Ok(Condition::And(
+                Box::new(Condition::Not(Box::new(Condition::Or(
+                    flag("a"),
+                    flag("b")
+                )))),
+                flag("c"),
+            ))
+        );
+
+        let flags = flags(&[("a", FlagValue::Bool(true))]);
+        assert!(!parse(r#"!(Flag("a") || Flag("b"))"#)
+            .unwrap()
+            .is_met(&flags, None));
+        assert!(parse(r#"!Flag("b") && (Flag("a") || Flag("b"))"#)
+            .unwrap()
+            .is_met(&flags, None));
+    }
+
+    #[test]
+    fn comparisons_by_type() {
+        let flags = flags(&[
+            ("coins", FlagValue::Int(3)),
+            ("mood", FlagValue::Text("angry".to_string())),
+            ("met", FlagValue::Bool(true)),
+        ]);
+        let is_met = |source: &str| parse(source).unwrap().is_met(&flags, None);
+
+        assert!(is_met(r#"Flag("coins") >= 3"#));
+        assert!(!is_met(r#"Flag("coins") > 3"#));
+        assert!(is_met(r#"Flag("coins") != -1"#));
+        assert!(is_met(r#"Flag("mood") == "angry""#));
+        assert!(is_met(r#"Flag("mood") < "calm""#));
+        assert!(is_met(r#"Flag("met") == true"#));
+
+        /* Different types are never equal, nor ordered */
+        assert!(!is_met(r#"Flag("coins") == "3""#));
+        assert!(is_met(r#"Flag("coins") != "3""#));
+        assert!(!is_met(r#"Flag("met") >= 1"#));
+    }
+
+    #[test]
+    fn unset_flags_compare_as_empty() {
+        let flags = GameFlags::default();
+        let is_met = |source: &str| parse(source).unwrap().is_met(&flags, None);
+
+        assert!(is_met(r#"Flag("coins") == 0"#));
+        assert!(is_met(r#"Flag("coins") < 1"#));
+        assert!(is_met(r#"Flag("mood") == """#));
+        assert!(is_met(r#"Flag("met") == false"#));
+        assert!(!is_met(r#"Flag("met")"#));
+        assert!(is_met(r#"!Flag("met")"#));
+    }
+
+    #[test]
+    fn has_item_counts() {
+        assert_eq!(
+            parse(r#"HasItem("coin", 3)"#),
+            Ok(Condition::HasItem(ItemId::from("coin"), 3))
+        );
+        assert_eq!(
+            parse(r#"HasItem("key")"#),
+            Ok(Condition::HasItem(ItemId::from("key"), 1))
+        );
+
+        let flags = GameFlags::default();
+        let mut inventory = Inventory::default();
+        inventory.add(&ItemId::from("coin"), 2, 99);
+        let condition = parse(r#"HasItem("coin", 3)"#).unwrap();
+        assert!(!condition.is_met(&flags, Some(&inventory)));
+        assert!(!condition.is_met(&flags, None));
+        inventory.add(&ItemId::from("coin"), 1, 99);
+        assert!(condition.is_met(&flags, Some(&inventory)));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(
+            parse(r#"Flag("a") && Flag("b)"#),
+            Err(ConditionParseError::UnterminatedText(18))
+        );
+        assert_eq!(
+            parse(r#"Flag("a") & Flag("b")"#),
+            Err(ConditionParseError::Unexpected {
+                found: "&".to_string(),
+                position: 10,
+            })
+        );
+        assert_eq!(
+            parse(r#"Flag("a") Flag("b")"#),
+            Err(ConditionParseError::Unexpected {
+                found: "Flag".to_string(),
+                position: 10,
+            })
+        );
+        assert_eq!(
+            parse(r#"HasItem("key", -1)"#),
+            Err(ConditionParseError::Unexpected {
+                found: "-1".to_string(),
+                position: 15,
+            })
+        );
+        assert_eq!(
+            parse(r#"Flag("a") &&"#),
+            Err(ConditionParseError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse(r#"HasKey("a")"#),
+            Err(ConditionParseError::UnknownCondition("HasKey".to_string()))
+        );
+    }
+}
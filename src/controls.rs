@@ -1,9 +1,11 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::{
-    level::{CurrentLevel, Interact, Level},
+    level::{CurrentLevel, DisarmTrap, Interact, Level, TILE_SIZE},
     movement::{FaceDirection, GridDirection, GridPosition, MoveForward},
-    GameState,
+    settings::{Action, Settings},
+    travel::{AutoExplore, Travel, TravelTo},
+    GameState, Modal,
 };
 
 pub struct ControlsPlugin;
@@ -13,12 +15,15 @@ impl Plugin for ControlsPlugin {
         app.add_systems(
             Update,
             (
+                cancel_travel_controls,
                 move_forwards_controls,
                 face_direction_controls,
                 interact_controls,
+                auto_explore_controls,
+                travel_to_controls,
             )
                 .chain()
-                .run_if(in_state(GameState::Playing)),
+                .run_if(in_state(GameState::Playing).and_then(in_state(Modal::None))),
         );
     }
 }
@@ -28,21 +33,23 @@ pub struct Controllable;
 
 fn face_direction_controls(
     key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
     directions: Query<(Entity, &GridDirection), With<Controllable>>,
     mut face_direction_evw: EventWriter<FaceDirection>,
 ) {
     for (entity, direction) in &directions {
-        if key_input.just_pressed(KeyCode::ArrowRight) {
+        let key_bindings = &settings.key_bindings;
+        if key_input.just_pressed(key_bindings.key(Action::TurnRight)) {
             face_direction_evw.send(FaceDirection {
                 entity,
                 direction: direction.right(),
             });
-        } else if key_input.just_pressed(KeyCode::ArrowDown) {
+        } else if key_input.just_pressed(key_bindings.key(Action::TurnAround)) {
             face_direction_evw.send(FaceDirection {
                 entity,
                 direction: direction.back(),
             });
-        } else if key_input.just_pressed(KeyCode::ArrowLeft) {
+        } else if key_input.just_pressed(key_bindings.key(Action::TurnLeft)) {
             face_direction_evw.send(FaceDirection {
                 entity,
                 direction: direction.left(),
@@ -53,11 +60,12 @@ fn face_direction_controls(
 
 fn move_forwards_controls(
     key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
     grid_positions: Query<Entity, (With<Controllable>, With<GridPosition>)>,
     mut move_forward_evw: EventWriter<MoveForward>,
 ) {
     for entity in &grid_positions {
-        if key_input.just_pressed(KeyCode::ArrowUp) {
+        if key_input.just_pressed(settings.key_bindings.key(Action::MoveForward)) {
             move_forward_evw.send(MoveForward { entity });
         }
     }
@@ -66,19 +74,27 @@ fn move_forwards_controls(
 /* TODO: I would much rather get the entity here and send that in the Interact event */
 fn interact_controls(
     key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
     controllables: Query<(Entity, &GridPosition, &GridDirection), With<Controllable>>,
     current_level: Res<CurrentLevel>,
     level_assets: Res<Assets<Level>>,
     mut interact_evw: EventWriter<Interact>,
+    mut disarm_trap_evw: EventWriter<DisarmTrap>,
 ) {
     for (entity, grid_position, direction) in &controllables {
-        if key_input.just_pressed(KeyCode::Space) {
+        if key_input.just_pressed(settings.key_bindings.key(Action::Interact)) {
             if let Ok(interact_position) = grid_position.next(direction) {
                 if let Some(level) = level_assets.get(&current_level.0) {
                     if let Some(interactable) = level.interactables.get(&interact_position) {
                         interact_evw.send(Interact {
                             source: entity,
                             target: interactable.clone(),
+                            position: interact_position,
+                        });
+                    } else if level.trap(&interact_position).is_some() {
+                        disarm_trap_evw.send(DisarmTrap {
+                            entity,
+                            position: interact_position,
                         });
                     }
                 }
@@ -86,3 +102,149 @@ fn interact_controls(
         }
     }
 }
+
+fn auto_explore_controls(
+    key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    controllables: Query<Entity, (With<Controllable>, With<GridPosition>)>,
+    mut auto_explore_evw: EventWriter<AutoExplore>,
+) {
+    for entity in &controllables {
+        if key_input.just_pressed(settings.key_bindings.key(Action::AutoExplore)) {
+            auto_explore_evw.send(AutoExplore { entity });
+        }
+    }
+}
+
+/// Any other key press takes back control from travelling
+fn cancel_travel_controls(
+    mut commands: Commands,
+    key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    travellers: Query<Entity, (With<Controllable>, With<Travel>)>,
+) {
+    let auto_explore = settings.key_bindings.key(Action::AutoExplore);
+    if key_input
+        .get_just_pressed()
+        .any(|key_code| *key_code != auto_explore)
+    {
+        for entity in &travellers {
+            commands.entity(entity).remove::<Travel>();
+        }
+    }
+}
+
+/// Clicking a cell of the floor travels there
+/// The floor cell under the cursor, walls in the way are looked through
+fn clicked_cell(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+) -> Option<GridPosition> {
+    let ray = camera.viewport_to_world(camera_transform, cursor)?;
+    /* The camera is at the height of the grid, the floor tiles lie half a tile below it */
+    let floor = Vec3::new(0.0, -TILE_SIZE / 2.0, 0.0);
+    let distance = ray.intersect_plane(floor, InfinitePlane3d::new(Vec3::Y))?;
+    let point = ray.get_point(distance);
+    let (x, y) = ((point.x / TILE_SIZE).round(), (point.z / TILE_SIZE).round());
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+
+    Some(GridPosition {
+        x: x as usize,
+        y: y as usize,
+    })
+}
+
+fn travel_to_controls(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    controllables: Query<Entity, (With<Controllable>, With<GridPosition>)>,
+    mut travel_to_evw: EventWriter<TravelTo>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+
+    for (camera, camera_transform) in &cameras {
+        let Some(target) = clicked_cell(camera, camera_transform, cursor) else {
+            continue;
+        };
+        for entity in &controllables {
+            travel_to_evw.send(TravelTo { entity, target });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        render::camera::{camera_system, ManualTextureViews},
+        window::{WindowCreated, WindowResized, WindowResolution, WindowScaleFactorChanged},
+    };
+
+    use super::*;
+
+    /// A camera looking north from the cell, with its projection computed for an 800x600 window
+    fn camera_at(position: GridPosition) -> (Camera, GlobalTransform) {
+        let mut app = App::new();
+        app.add_event::<WindowResized>()
+            .add_event::<WindowCreated>()
+            .add_event::<WindowScaleFactorChanged>()
+            .add_event::<AssetEvent<Image>>()
+            .init_resource::<Assets<Image>>()
+            .init_resource::<ManualTextureViews>()
+            .add_systems(Update, camera_system::<Projection>);
+        app.world_mut().spawn((
+            Window {
+                resolution: WindowResolution::new(800.0, 600.0),
+                ..default()
+            },
+            PrimaryWindow,
+        ));
+
+        let transform = Transform::from_translation((&position).into())
+            .looking_to(GridDirection::North, Vec3::Y);
+        let camera = app
+            .world_mut()
+            .spawn(Camera3dBundle {
+                transform,
+                global_transform: transform.into(),
+                ..default()
+            })
+            .id();
+        app.update();
+
+        let camera = app.world_mut().entity_mut(camera);
+        (
+            camera.get::<Camera>().unwrap().clone(),
+            *camera.get::<GlobalTransform>().unwrap(),
+        )
+    }
+
+    #[test]
+    fn clicks_pick_the_floor_cell_under_the_cursor() {
+        let (camera, transform) = camera_at(GridPosition { x: 2, y: 4 });
+
+        /* Below the middle of the screen is the floor a little ahead */
+        assert_eq!(
+            clicked_cell(&camera, &transform, Vec2::new(400.0, 450.0)),
+            Some(GridPosition { x: 2, y: 2 })
+        );
+        /* The floor right in front */
+        assert_eq!(
+            clicked_cell(&camera, &transform, Vec2::new(400.0, 599.0)),
+            Some(GridPosition { x: 2, y: 3 })
+        );
+        /* Above the horizon there is no floor */
+        assert_eq!(
+            clicked_cell(&camera, &transform, Vec2::new(400.0, 100.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    condition::Condition,
+    flags::{FlagValue, GameFlags},
+    inventory::Inventory,
+    item::{ItemId, Items, PickUp},
+    level::{Interact, Interactable, LevelError, Signal, SignalAction},
+    loading::DialogueAssets,
+    localization::{LocalizedText, Texts},
+    message_log::{LogMessage, MessageCategory},
+    Modal,
+};
+
+pub struct DialoguePlugin;
+
+/// This plugin runs [`Dialogue`]s with NPCs, the player picks their answers with the number keys
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Dialogue>()
+            .init_asset_loader::<DialogueAssetLoader>()
+            .init_resource::<Conversation>()
+            .add_systems(Update, start_dialogue.run_if(in_state(Modal::None)))
+            .add_systems(OnExit(Modal::Dialogue), end_dialogue)
+            .add_systems(
+                Update,
+                (choose_answer, update_dialogue_panel)
+                    .chain()
+                    .run_if(in_state(Modal::Dialogue)),
+            );
+    }
+}
+
+/// Someone to talk to, interacting with them starts their dialogue
+#[derive(Debug, Clone, Deserialize)]
+pub struct Npc {
+    /// Path of the dialogue, e.g. `dialogue/gatekeeper.dialogue`
+    pub dialogue: String,
+}
+
+/// A tree of lines of an NPC and the answers the player can give
+#[derive(Debug, Deserialize, Asset, TypePath)]
+pub struct Dialogue {
+    pub speaker: LocalizedText,
+    /// The node the dialogue starts with
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DialogueNode {
+    pub text: LocalizedText,
+    /// Applied whenever the node is reached
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    /// Without choices the player can only leave
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DialogueChoice {
+    pub text: LocalizedText,
+    /// The choice is only offered when the condition is met
+    #[serde(default)]
+    pub requires: Option<Condition>,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    /// The node to continue with, the dialogue ends without one
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum DialogueEffect {
+    GiveItem(ItemId),
+    TakeItem(ItemId),
+    SetFlag(String, FlagValue),
+    ClearFlag(String),
+    /// Counts an integer flag up, or down with a negative amount
+    AddToFlag(String, i64),
+    /// Switches a mechanism, e.g. opens a door
+    Signal(String, SignalAction),
+}
+
+impl Dialogue {
+    fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.get(id)
+    }
+}
+
+/// The dialogue running while in [`Modal::Dialogue`]
+#[derive(Debug, Default, Resource)]
+struct Conversation(Option<ConversationState>);
+
+#[derive(Debug)]
+struct ConversationState {
+    dialogue: Handle<Dialogue>,
+    node: String,
+    /// The entity talking to the NPC, whose inventory the dialogue uses
+    listener: Entity,
+}
+
+#[derive(Component)]
+struct DialoguePanel;
+
+/// What conditions check and effects change
+#[derive(SystemParam)]
+struct DialogueContext<'w, 's> {
+    flags: ResMut<'w, GameFlags>,
+    inventories: Query<'w, 's, &'static mut Inventory>,
+    items: Items<'w>,
+    texts: Texts<'w>,
+    pick_up_evw: EventWriter<'w, PickUp>,
+    signal_evw: EventWriter<'w, Signal>,
+    log_message_evw: EventWriter<'w, LogMessage>,
+}
+
+impl DialogueContext<'_, '_> {
+    fn is_met(&self, listener: Entity, requires: Option<&Condition>) -> bool {
+        requires.is_none_or(|condition| {
+            condition.is_met(&self.flags, self.inventories.get(listener).ok())
+        })
+    }
+
+    fn apply(&mut self, listener: Entity, effects: &[DialogueEffect]) {
+        for effect in effects {
+            match effect {
+                DialogueEffect::GiveItem(item) => {
+                    self.pick_up_evw.send(PickUp {
+                        entity: listener,
+                        item: item.clone(),
+                        count: 1,
+                    });
+                }
+                DialogueEffect::TakeItem(item) => {
+                    if let Ok(mut inventory) = self.inventories.get_mut(listener) {
+                        if inventory.remove(item, 1) {
+                            self.log_message_evw.send(LogMessage::new(
+                                MessageCategory::Interaction,
+                                format!("You hand over: {}", self.items.name(item, &self.texts)),
+                            ));
+                        }
+                    }
+                }
+                DialogueEffect::SetFlag(flag, value) => self.flags.set(flag, value.clone()),
+                DialogueEffect::ClearFlag(flag) => self.flags.clear(flag),
+                DialogueEffect::AddToFlag(flag, amount) => self.flags.add(flag, *amount),
+                DialogueEffect::Signal(target, action) => {
+                    self.signal_evw.send(Signal {
+                        target: target.clone(),
+                        action: *action,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The choices of the node whose conditions are met
+    fn choices<'a>(&self, listener: Entity, node: &'a DialogueNode) -> Vec<&'a DialogueChoice> {
+        node.choices
+            .iter()
+            .filter(|choice| self.is_met(listener, choice.requires.as_ref()))
+            .collect()
+    }
+}
+
+const ANSWER_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+fn start_dialogue(
+    mut interact_evr: EventReader<Interact>,
+    dialogue_assets: Res<DialogueAssets>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut conversation: ResMut<Conversation>,
+    mut context: DialogueContext,
+    mut next_modal: ResMut<NextState<Modal>>,
+    mut level_error_evw: EventWriter<LevelError>,
+) {
+    for event in interact_evr.read() {
+        let Interactable::Npc(npc) = &event.target else {
+            continue;
+        };
+        let Some(handle) = dialogue_assets.dialogues.get(&npc.dialogue) else {
+            level_error_evw.send(LevelError::UnknownDialogue(npc.dialogue.clone()));
+            continue;
+        };
+        let Some(dialogue) = dialogues.get(handle) else {
+            continue;
+        };
+        let Some(node) = dialogue.node(&dialogue.start) else {
+            continue;
+        };
+
+        context.apply(event.source, &node.effects);
+        conversation.0 = Some(ConversationState {
+            dialogue: handle.clone(),
+            node: dialogue.start.clone(),
+            listener: event.source,
+        });
+        next_modal.set(Modal::Dialogue);
+    }
+}
+
+/// Number keys pick an answer, Escape leaves the conversation
+fn choose_answer(
+    key_input: Res<ButtonInput<KeyCode>>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut conversation: ResMut<Conversation>,
+    mut context: DialogueContext,
+    mut next_modal: ResMut<NextState<Modal>>,
+) {
+    if key_input.just_pressed(KeyCode::Escape) {
+        next_modal.set(Modal::None);
+        return;
+    }
+    let Some(index) = ANSWER_KEYS
+        .iter()
+        .position(|key| key_input.just_pressed(*key))
+    else {
+        return;
+    };
+    let Some(state) = &conversation.0 else {
+        return;
+    };
+    let Some(dialogue) = dialogues.get(&state.dialogue) else {
+        return;
+    };
+    let Some(node) = dialogue.node(&state.node) else {
+        return;
+    };
+
+    let listener = state.listener;
+    let choices = context.choices(listener, node);
+    /* Nodes without choices only offer to leave */
+    if choices.is_empty() {
+        if index == 0 {
+            next_modal.set(Modal::None);
+        }
+        return;
+    }
+    let Some(choice) = choices.get(index) else {
+        return;
+    };
+
+    context.apply(listener, &choice.effects);
+    match choice
+        .next
+        .as_ref()
+        .and_then(|next| Some((next, dialogue.node(next)?)))
+    {
+        Some((next, next_node)) => {
+            context.apply(listener, &next_node.effects);
+            if let Some(state) = &mut conversation.0 {
+                state.node = next.clone();
+            }
+        }
+        None => next_modal.set(Modal::None),
+    }
+}
+
+fn update_dialogue_panel(
+    mut commands: Commands,
+    conversation: Res<Conversation>,
+    dialogues: Res<Assets<Dialogue>>,
+    context: DialogueContext,
+    texts: Texts,
+    panels: Query<Entity, With<DialoguePanel>>,
+) {
+    if !conversation.is_changed() && !panels.is_empty() {
+        return;
+    }
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(state) = &conversation.0 else {
+        return;
+    };
+    let Some(dialogue) = dialogues.get(&state.dialogue) else {
+        return;
+    };
+    let Some(node) = dialogue.node(&state.node) else {
+        return;
+    };
+
+    let mut answers = context
+        .choices(state.listener, node)
+        .into_iter()
+        .map(|choice| texts.get(&choice.text))
+        .collect::<Vec<_>>();
+    if answers.is_empty() {
+        answers.push("Leave".to_string());
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::FlexEnd,
+                    justify_content: JustifyContent::Center,
+                    padding: UiRect::bottom(Val::Px(180.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            DialoguePanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(640.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgba(0.05, 0.05, 0.08, 0.92).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        texts.get(&dialogue.speaker),
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::srgb(0.95, 0.85, 0.6),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        texts.get(&node.text),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                    for (index, answer) in answers.iter().enumerate().take(ANSWER_KEYS.len()) {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{}. {answer}", index + 1),
+                            TextStyle {
+                                font_size: 18.0,
+                                color: Color::srgb(0.6, 0.8, 1.0),
+                                ..default()
+                            },
+                        ));
+                    }
+                });
+        });
+}
+
+fn end_dialogue(
+    mut commands: Commands,
+    mut conversation: ResMut<Conversation>,
+    panels: Query<Entity, With<DialoguePanel>>,
+) {
+    conversation.0 = None;
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DialogueAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+    /// The start or a choice leads to a node that does not exist
+    #[error("There is no node {0}")]
+    UnknownNode(String),
+}
+
+#[derive(Default)]
+pub struct DialogueAssetLoader;
+
+impl AssetLoader for DialogueAssetLoader {
+    type Asset = Dialogue;
+    type Settings = ();
+    type Error = DialogueAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let custom_asset = ron::de::from_bytes::<Dialogue>(&bytes)?;
+        if let Some(unknown) = std::iter::once(&custom_asset.start)
+            .chain(
+                custom_asset
+                    .nodes
+                    .values()
+                    .flat_map(|node| &node.choices)
+                    .filter_map(|choice| choice.next.as_ref()),
+            )
+            .find(|id| !custom_asset.nodes.contains_key(*id))
+        {
+            return Err(DialogueAssetLoaderError::UnknownNode(unknown.clone()));
+        }
+        Ok(custom_asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue"]
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+
+pub struct FlagsPlugin;
+
+/// This plugin keeps the [`GameFlags`] of the running game, so story progress can be remembered
+impl Plugin for FlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameFlags>()
+            .add_systems(OnEnter(GameState::Menu), clear_flags);
+    }
+}
+
+/// The value of a flag, written as a plain `true`, `3` or `"open"` in assets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlagValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+}
+
+impl FlagValue {
+    /// Whether the flag counts as set, `false`, `0` and `""` do not
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            FlagValue::Bool(value) => *value,
+            FlagValue::Int(value) => *value != 0,
+            FlagValue::Text(value) => !value.is_empty(),
+        }
+    }
+}
+
+/// Named facts about the story so far, like having talked to someone or how often
+///
+/// Saved games store them, see [`SavedGame`](crate::save::SavedGame)
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GameFlags(HashMap<String, FlagValue>);
+
+impl GameFlags {
+    pub fn get(&self, flag: &str) -> Option<&FlagValue> {
+        self.0.get(flag)
+    }
+
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.get(flag).is_some_and(FlagValue::is_truthy)
+    }
+
+    pub fn set(&mut self, flag: impl Into<String>, value: FlagValue) {
+        self.0.insert(flag.into(), value);
+    }
+
+    pub fn clear(&mut self, flag: &str) {
+        self.0.remove(flag);
+    }
+
+    /// Counts an integer flag up or down, flags that are not integers start over at zero
+    pub fn add(&mut self, flag: &str, amount: i64) {
+        let value = match self.get(flag) {
+            Some(FlagValue::Int(value)) => value.saturating_add(amount),
+            _ => amount,
+        };
+        self.set(flag, FlagValue::Int(value));
+    }
+}
+
+fn clear_flags(mut flags: ResMut<GameFlags>) {
+    *flags = GameFlags::default();
+}
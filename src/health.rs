@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::ScreenShake,
+    level::Player,
+    message_log::{LogMessage, MessageCategory},
+    toast::Toast,
+    GameState,
+};
+
+pub struct HealthPlugin;
+
+/// This plugin applies [`Damage`] and ends the game when the player dies
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Damage>()
+            .add_event::<Defeated>()
+            .add_systems(Update, apply_damage.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Debug, Component, Clone, Copy, Serialize, Deserialize)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max > 0 {
+            self.current as f32 / self.max as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Event)]
+pub struct Damage {
+    pub entity: Entity,
+    pub amount: u32,
+}
+
+/// Sent when anything but the player runs out of health
+#[derive(Debug, Event)]
+pub struct Defeated {
+    pub entity: Entity,
+}
+
+/// Damage to the player shakes the camera, more the more health is lost
+fn apply_damage(
+    mut damage_evr: EventReader<Damage>,
+    mut healths: Query<(&mut Health, Has<Player>)>,
+    mut screen_shake_evw: EventWriter<ScreenShake>,
+    mut toast_evw: EventWriter<Toast>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut defeated_evw: EventWriter<Defeated>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in damage_evr.read() {
+        let Ok((mut health, is_player)) = healths.get_mut(event.entity) else {
+            continue;
+        };
+
+        let was_alive = health.current > 0;
+        health.current = health.current.saturating_sub(event.amount);
+        if !is_player {
+            if was_alive && health.current == 0 {
+                defeated_evw.send(Defeated {
+                    entity: event.entity,
+                });
+            }
+            continue;
+        }
+
+        log_message_evw.send(LogMessage::new(
+            MessageCategory::Combat,
+            format!("You take {} damage", event.amount),
+        ));
+        screen_shake_evw.send(ScreenShake {
+            trauma: 0.3 + 0.7 * event.amount as f32 / health.max.max(1) as f32,
+        });
+        if health.current == 0 {
+            info!("The player died");
+            toast_evw.send(Toast("You died".to_string()));
+            next_state.set(GameState::Menu);
+        }
+    }
+}
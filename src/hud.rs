@@ -0,0 +1,373 @@
+use bevy::{
+    color::palettes::css::{CRIMSON, ORANGE},
+    prelude::*,
+};
+
+use crate::{
+    campaign::{level_path, ActiveCampaign, Campaign},
+    health::Health,
+    inventory::Inventory,
+    item::Items,
+    level::{CurrentLevel, LightFuel, Player},
+    localization::Texts,
+    message_log::spawn_message_panel,
+    movement::GridDirection,
+    GameState,
+};
+
+pub struct HudPlugin;
+
+/// This plugin draws the heads-up display while playing, it is hidden in the pause menu
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), show_hud)
+            .add_systems(OnExit(GameState::Playing), hide_hud)
+            .add_systems(OnEnter(GameState::Menu), cleanup_hud)
+            .add_systems(
+                Update,
+                (
+                    update_compass,
+                    update_level_name,
+                    update_bars,
+                    update_quick_slots,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct Compass;
+
+#[derive(Component)]
+struct LevelName;
+
+#[derive(Component)]
+enum Bar {
+    Health,
+    Light,
+}
+
+#[derive(Component)]
+struct QuickSlot(usize);
+
+/// The icon of the item in a quick slot, hidden for items without one
+#[derive(Component)]
+struct QuickSlotIcon(usize);
+
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const PANEL_COLOR: Color = Color::srgba(0.05, 0.05, 0.05, 0.7);
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+fn show_hud(mut commands: Commands, mut huds: Query<&mut Visibility, With<Hud>>) {
+    if !huds.is_empty() {
+        for mut visibility in &mut huds {
+            *visibility = Visibility::Inherited;
+        }
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            Hud,
+        ))
+        .with_children(|parent| {
+            /* Top: level name and compass */
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("", text_style(22.0)).with_style(Style {
+                            width: Val::Px(250.0),
+                            ..default()
+                        }),
+                        LevelName,
+                    ));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.0), Val::Px(4.0)),
+                                ..default()
+                            },
+                            background_color: PANEL_COLOR.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_sections([
+                                    TextSection::new("", text_style(20.0)),
+                                    TextSection::new("", text_style(28.0)),
+                                    TextSection::new("", text_style(20.0)),
+                                ]),
+                                Compass,
+                            ));
+                        });
+                    /* Balances the level name, so the compass is centered */
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                });
+
+            /* Bottom: resources and quick slots */
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::FlexEnd,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(320.0),
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(4.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            spawn_bar(parent, Bar::Health, CRIMSON.into());
+                            spawn_bar(parent, Bar::Light, ORANGE.into());
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(4.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for slot in 0..Inventory::QUICK_SLOTS {
+                                spawn_quick_slot(parent, slot);
+                            }
+                        });
+                    spawn_message_panel(parent);
+                });
+        });
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, bar: Bar, color: Color) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Px(14.0),
+                ..default()
+            },
+            background_color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                bar,
+            ));
+        });
+}
+
+fn spawn_quick_slot(parent: &mut ChildBuilder, slot: usize) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(56.0),
+                height: Val::Px(56.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("{}", slot + 1),
+                TextStyle {
+                    font_size: 12.0,
+                    color: Color::srgb(0.6, 0.6, 0.6),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                ImageBundle {
+                    style: Style {
+                        width: Val::Px(20.0),
+                        height: Val::Px(20.0),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                QuickSlotIcon(slot),
+            ));
+            parent.spawn((
+                TextBundle::from_section("", text_style(12.0)),
+                QuickSlot(slot),
+            ));
+        });
+}
+
+fn hide_hud(mut huds: Query<&mut Visibility, With<Hud>>) {
+    for mut visibility in &mut huds {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn cleanup_hud(mut commands: Commands, huds: Query<Entity, With<Hud>>) {
+    for entity in &huds {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn direction_letter(direction: GridDirection) -> &'static str {
+    match direction {
+        GridDirection::North => "N",
+        GridDirection::East => "E",
+        GridDirection::South => "S",
+        GridDirection::West => "W",
+    }
+}
+
+/* The HUD is filled as soon as it is spawned, afterwards only on changes */
+fn update_compass(
+    players: Query<Ref<GridDirection>, With<Player>>,
+    mut compasses: Query<(Ref<Compass>, &mut Text)>,
+) {
+    let Ok(direction) = players.get_single() else {
+        return;
+    };
+
+    for (compass, mut text) in &mut compasses {
+        if !direction.is_changed() && !compass.is_added() {
+            continue;
+        }
+        text.sections[0].value = format!("{}   ", direction_letter(direction.left()));
+        text.sections[1].value = direction_letter(*direction).to_string();
+        text.sections[2].value = format!("   {}", direction_letter(direction.right()));
+    }
+}
+
+fn update_level_name(
+    current_level: Res<CurrentLevel>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+    mut level_names: Query<(Ref<LevelName>, &mut Text)>,
+) {
+    for (level_name, mut text) in &mut level_names {
+        if !current_level.is_changed() && !level_name.is_added() {
+            continue;
+        }
+
+        let path = level_path(&current_level.0);
+        let title = campaigns
+            .get(&active_campaign.0)
+            .zip(path.as_deref())
+            .and_then(|(campaign, path)| campaign.level(path))
+            .map(|level| level.title.clone());
+        text.sections[0].value = title.or(path).unwrap_or_default();
+    }
+}
+
+fn update_bars(
+    players: Query<(Ref<Health>, Ref<LightFuel>), With<Player>>,
+    mut bars: Query<(Ref<Bar>, &mut Style)>,
+) {
+    let Ok((health, fuel)) = players.get_single() else {
+        return;
+    };
+
+    for (bar, mut style) in &mut bars {
+        let fraction = match *bar {
+            Bar::Health if health.is_changed() || bar.is_added() => health.fraction(),
+            Bar::Light if fuel.is_changed() || bar.is_added() => fuel.fraction(),
+            _ => continue,
+        };
+        style.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+fn update_quick_slots(
+    players: Query<Ref<Inventory>, With<Player>>,
+    items: Items,
+    texts: Texts,
+    mut quick_slots: Query<(Ref<QuickSlot>, &mut Text)>,
+    mut quick_slot_icons: Query<(Ref<QuickSlotIcon>, &mut UiImage, &mut Visibility)>,
+) {
+    let Ok(inventory) = players.get_single() else {
+        return;
+    };
+
+    for (quick_slot, mut text) in &mut quick_slots {
+        if !inventory.is_changed() && !quick_slot.is_added() {
+            continue;
+        }
+        text.sections[0].value = inventory
+            .slots
+            .get(quick_slot.0)
+            .map(|stack| items.name_with_count(&stack.item, stack.count, &texts))
+            .unwrap_or_default();
+    }
+
+    for (quick_slot_icon, mut image, mut visibility) in &mut quick_slot_icons {
+        if !inventory.is_changed() && !quick_slot_icon.is_added() {
+            continue;
+        }
+        let icon = inventory
+            .slots
+            .get(quick_slot_icon.0)
+            .and_then(|stack| items.get(&stack.item)?.icon.clone());
+        match icon {
+            Some(icon) => {
+                image.texture = icon;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::item::ItemId;
+
+/// Items an entity carries, the first [`Inventory::QUICK_SLOTS`] are at hand
+#[derive(Debug, Default, Clone, Component, Serialize, Deserialize)]
+pub struct Inventory {
+    pub slots: Vec<ItemStack>,
+}
+
+/// Items of one kind in a slot, never more than the stack limit of the item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+impl Inventory {
+    pub const QUICK_SLOTS: usize = 8;
+
+    /// Fills up the stacks of the item before starting new ones
+    pub fn add(&mut self, item: &ItemId, count: u32, stack_limit: u32) {
+        let mut left = count;
+        for stack in self.slots.iter_mut().filter(|stack| stack.item == *item) {
+            let added = left.min(stack_limit.saturating_sub(stack.count));
+            stack.count += added;
+            left -= added;
+        }
+        while left > 0 {
+            let added = left.min(stack_limit.max(1));
+            self.slots.push(ItemStack {
+                item: item.clone(),
+                count: added,
+            });
+            left -= added;
+        }
+    }
+
+    pub fn count(&self, item: &ItemId) -> u32 {
+        self.slots
+            .iter()
+            .filter(|stack| stack.item == *item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Whether enough of the item was carried, nothing is removed otherwise
+    pub fn remove(&mut self, item: &ItemId, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+
+        /* The last stacks are emptied first, so the quick slots stay in place */
+        let mut left = count;
+        for stack in self
+            .slots
+            .iter_mut()
+            .rev()
+            .filter(|stack| stack.item == *item)
+        {
+            let removed = left.min(stack.count);
+            stack.count -= removed;
+            left -= removed;
+        }
+        self.slots.retain(|stack| stack.count > 0);
+        true
+    }
+}
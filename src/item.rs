@@ -0,0 +1,251 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    health::Health,
+    inventory::Inventory,
+    level::{LevelError, LightFuel},
+    loading::ItemAssets,
+    localization::{LocalizedText, Texts},
+    message_log::{LogMessage, MessageCategory},
+    GameState,
+};
+
+pub struct ItemPlugin;
+
+/// This plugin loads the [`ItemDatabase`] and hands out items that are [`PickUp`]ed
+impl Plugin for ItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ItemDatabase>()
+            .init_asset_loader::<ItemDatabaseAssetLoader>()
+            .add_event::<PickUp>()
+            .add_systems(Update, pick_up.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Identifies an item in the [`ItemDatabase`], e.g. `"key"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub String);
+
+impl std::fmt::Display for ItemId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ItemCategory {
+    Key,
+    /// Used up when picked up, instead of going into the inventory
+    Consumable,
+    Quest,
+    Misc,
+}
+
+/// What a consumable does when it is picked up
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum ItemEffect {
+    /// Refills the fuel of the light carried by the looter
+    RefillLight,
+    Heal(u32),
+}
+
+/// An item as written in the database file, paths are relative to the assets folder
+#[derive(Debug, Deserialize)]
+struct ItemDefinition {
+    name: LocalizedText,
+    #[serde(default)]
+    icon: Option<String>,
+    /// e.g. `models/KayKit_DungeonRemastered_1.0_FREE/key.gltf.glb#Scene0`
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default = "ItemDefinition::default_stack_limit")]
+    stack_limit: u32,
+    category: ItemCategory,
+    #[serde(default)]
+    effects: Vec<ItemEffect>,
+}
+
+impl ItemDefinition {
+    fn default_stack_limit() -> u32 {
+        1
+    }
+}
+
+#[derive(Debug)]
+pub struct Item {
+    pub name: LocalizedText,
+    /// Shown in the quick slots
+    pub icon: Option<Handle<Image>>,
+    /// Shown on chests that hold the item
+    pub model: Option<Handle<Scene>>,
+    /// How many of the item fit into one inventory slot
+    pub stack_limit: u32,
+    pub category: ItemCategory,
+    pub effects: Vec<ItemEffect>,
+}
+
+/// All items there are by their id, icons and models are loaded along with it
+#[derive(Debug, Default, Asset, TypePath)]
+pub struct ItemDatabase {
+    items: HashMap<ItemId, Item>,
+}
+
+/// Looks up items in the [`ItemDatabase`]
+#[derive(SystemParam)]
+pub struct Items<'w> {
+    item_assets: Res<'w, ItemAssets>,
+    databases: Res<'w, Assets<ItemDatabase>>,
+}
+
+impl Items<'_> {
+    pub fn get(&self, id: &ItemId) -> Option<&Item> {
+        self.databases
+            .get(&self.item_assets.database)
+            .and_then(|database| database.items.get(id))
+    }
+
+    /// Falls back to the id, so missing items stand out
+    pub fn name(&self, id: &ItemId, texts: &Texts) -> String {
+        match self.get(id) {
+            Some(item) => texts.get(&item.name),
+            None => id.to_string(),
+        }
+    }
+
+    /// The name with the count, if there is more than one
+    pub fn name_with_count(&self, id: &ItemId, count: u32, texts: &Texts) -> String {
+        match count {
+            1 => self.name(id, texts),
+            count => format!("{count} {}", self.name(id, texts)),
+        }
+    }
+}
+
+/// Gives `count` of the item to the entity, consumables are used right away
+#[derive(Debug, Event)]
+pub struct PickUp {
+    pub entity: Entity,
+    pub item: ItemId,
+    pub count: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pick_up(
+    mut pick_up_evr: EventReader<PickUp>,
+    items: Items,
+    texts: Texts,
+    mut inventories: Query<&mut Inventory>,
+    mut fuels: Query<&mut LightFuel>,
+    mut healths: Query<&mut Health>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut level_error_evw: EventWriter<LevelError>,
+) {
+    for event in pick_up_evr.read() {
+        let Some(item) = items.get(&event.item) else {
+            level_error_evw.send(LevelError::UnknownItem(event.item.to_string()));
+            continue;
+        };
+
+        let name = items.name_with_count(&event.item, event.count, &texts);
+        if item.category != ItemCategory::Consumable {
+            if let Ok(mut inventory) = inventories.get_mut(event.entity) {
+                inventory.add(&event.item, event.count, item.stack_limit);
+            }
+            log_message_evw.send(LogMessage::new(
+                MessageCategory::Interaction,
+                format!("You receive: {name}"),
+            ));
+            continue;
+        }
+
+        for effect in &item.effects {
+            match effect {
+                ItemEffect::RefillLight => {
+                    if let Ok(mut fuel) = fuels.get_mut(event.entity) {
+                        let max = fuel.max;
+                        fuel.refill(max);
+                    }
+                }
+                ItemEffect::Heal(amount) => {
+                    if let Ok(mut health) = healths.get_mut(event.entity) {
+                        let amount = amount.saturating_mul(event.count);
+                        health.current = health.current.saturating_add(amount).min(health.max);
+                    }
+                }
+            }
+        }
+        log_message_evw.send(LogMessage::new(
+            MessageCategory::Interaction,
+            format!("You use: {name}"),
+        ));
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ItemDatabaseAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+    /// Items must fit into the inventory at least once
+    #[error("The stack limit of {0} must be at least 1")]
+    EmptyStack(ItemId),
+}
+
+#[derive(Default)]
+pub struct ItemDatabaseAssetLoader;
+
+impl AssetLoader for ItemDatabaseAssetLoader {
+    type Asset = ItemDatabase;
+    type Settings = ();
+    type Error = ItemDatabaseAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let definitions = ron::de::from_bytes::<HashMap<ItemId, ItemDefinition>>(&bytes)?;
+
+        let mut items = HashMap::default();
+        for (id, definition) in definitions {
+            if definition.stack_limit == 0 {
+                return Err(ItemDatabaseAssetLoaderError::EmptyStack(id));
+            }
+            let item = Item {
+                name: definition.name,
+                icon: definition.icon.map(|path| load_context.load(path)),
+                model: definition.model.map(|path| load_context.load(path)),
+                stack_limit: definition.stack_limit,
+                category: definition.category,
+                effects: definition.effects,
+            };
+            items.insert(id, item);
+        }
+        Ok(ItemDatabase { items })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["items"]
+    }
+}
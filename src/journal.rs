@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    level::{CurrentLevel, Interact, Interactable, Level},
+    loading::SceneAssets,
+    localization::{LocalizedText, Texts},
+    message_log::{LogMessage, MessageCategory},
+    movement::GridPosition,
+    settings::{Action, Settings},
+    GameState, Modal,
+};
+
+pub struct JournalPlugin;
+
+/// This plugin shows [`Readable`]s in a panel when they are read and records them in the [`Journal`]
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Journal>()
+            .init_resource::<OpenReadable>()
+            .add_systems(OnEnter(GameState::Menu), clear_journal)
+            .add_systems(OnEnter(Modal::Reading), show_reading_panel)
+            .add_systems(OnExit(Modal::Reading), cleanup_reading_panel)
+            .add_systems(Update, read.run_if(in_state(Modal::None)))
+            .add_systems(Update, close_reading_panel.run_if(in_state(Modal::Reading)));
+    }
+}
+
+/// Signs, scrolls and inscriptions, which are read by interacting with them
+#[derive(Debug, Clone, Deserialize)]
+pub struct Readable {
+    pub kind: ReadableKind,
+    /// Defaults to the name of the kind
+    #[serde(default)]
+    pub title: Option<LocalizedText>,
+    pub text: LocalizedText,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum ReadableKind {
+    Sign,
+    Scroll,
+    Inscription,
+}
+
+impl Readable {
+    pub fn bundle(&self, scene_assets: &SceneAssets, transform: Transform) -> SceneBundle {
+        match self.kind {
+            ReadableKind::Sign => scene_assets.sign(transform),
+            ReadableKind::Scroll => scene_assets.scroll(transform),
+            ReadableKind::Inscription => scene_assets.inscription(transform),
+        }
+    }
+
+    pub fn title(&self, texts: &Texts) -> String {
+        match &self.title {
+            Some(title) => texts.get(title),
+            None => format!("{:?}", self.kind),
+        }
+    }
+}
+
+/// A readable the player has read, the text is looked up again when shown
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub level: AssetId<Level>,
+    pub position: GridPosition,
+    pub readable: Readable,
+}
+
+/// Everything the player has read during this game, oldest first
+#[derive(Debug, Default, Resource)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Whether the entry is new, reading the same thing twice only records it once
+    fn record(&mut self, entry: JournalEntry) -> bool {
+        let known = self
+            .entries
+            .iter()
+            .any(|known| known.level == entry.level && known.position == entry.position);
+        if !known {
+            self.entries.push(entry);
+        }
+        !known
+    }
+}
+
+/// The readable shown while in [`Modal::Reading`]
+#[derive(Debug, Default, Resource)]
+struct OpenReadable(Option<Readable>);
+
+#[derive(Component)]
+struct ReadingPanel;
+
+fn clear_journal(mut journal: ResMut<Journal>) {
+    *journal = Journal::default();
+}
+
+fn read(
+    mut interact_evr: EventReader<Interact>,
+    current_level: Res<CurrentLevel>,
+    mut journal: ResMut<Journal>,
+    mut open_readable: ResMut<OpenReadable>,
+    mut next_modal: ResMut<NextState<Modal>>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    for event in interact_evr.read() {
+        let Interactable::Readable(readable) = &event.target else {
+            continue;
+        };
+
+        if journal.record(JournalEntry {
+            level: current_level.id(),
+            position: event.position,
+            readable: readable.clone(),
+        }) {
+            log_message_evw.send(LogMessage::new(
+                MessageCategory::Interaction,
+                "Recorded in your journal",
+            ));
+        }
+        open_readable.0 = Some(readable.clone());
+        next_modal.set(Modal::Reading);
+    }
+}
+
+fn show_reading_panel(
+    mut commands: Commands,
+    open_readable: Res<OpenReadable>,
+    settings: Res<Settings>,
+    texts: Texts,
+) {
+    let Some(readable) = &open_readable.0 else {
+        return;
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ReadingPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Px(600.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgba(0.12, 0.1, 0.08, 0.95).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        readable.title(&texts),
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::srgb(0.95, 0.85, 0.6),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        texts.get(&readable.text),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        format!(
+                            "Press {:?} or Escape to close",
+                            settings.key_bindings.key(Action::Interact)
+                        ),
+                        TextStyle {
+                            font_size: 14.0,
+                            color: Color::srgb(0.6, 0.6, 0.6),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+/// The interact key closes the panel again, as does Escape
+fn close_reading_panel(
+    key_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut next_modal: ResMut<NextState<Modal>>,
+) {
+    if key_input.any_just_pressed([KeyCode::Escape, settings.key_bindings.key(Action::Interact)]) {
+        next_modal.set(Modal::None);
+    }
+}
+
+fn cleanup_reading_panel(
+    mut commands: Commands,
+    mut open_readable: ResMut<OpenReadable>,
+    panels: Query<Entity, With<ReadingPanel>>,
+) {
+    open_readable.0 = None;
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
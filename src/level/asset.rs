@@ -1,9 +1,17 @@
 use serde::Deserialize;
 use thiserror::Error;
 
-use crate::movement::GridPosition;
+use crate::{audio::SoundEmitter, movement::GridPosition};
 
-use super::{interactables::Interactable, Tile};
+use super::{
+    interactables::Interactable,
+    light::Torch,
+    mechanism::{Bridge, PressurePlate},
+    state::LevelState,
+    trap::{Trap, TrapKind},
+    trigger::TileTrigger,
+    Tile,
+};
 
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
@@ -18,6 +26,191 @@ pub struct Level {
     pub start_pos: GridPosition,
     #[serde(default)]
     pub interactables: HashMap<GridPosition, Interactable>,
+    #[serde(default)]
+    pub torches: Vec<Torch>,
+    /// Factor for the intensity of the light the player carries, so level lighting matters
+    #[serde(default)]
+    pub carried_light: Option<f32>,
+    /// Path of the ambient loop, e.g. `audio/ambience/cave.wav`
+    #[serde(default)]
+    pub ambience: Option<String>,
+    #[serde(default)]
+    pub emitters: Vec<SoundEmitter>,
+    #[serde(default)]
+    pub bridges: Vec<Bridge>,
+    #[serde(default)]
+    pub pressure_plates: Vec<PressurePlate>,
+    /// Fired when an entity enters the cell
+    #[serde(default)]
+    pub triggers: HashMap<GridPosition, TileTrigger>,
+    #[serde(default)]
+    pub traps: Vec<Trap>,
+    /// Path of the script run on level events, e.g. `script/000.rhai`
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+impl Level {
+    pub fn tile(&self, position: &GridPosition) -> Option<&Tile> {
+        self.grid
+            .get(position.y)
+            .and_then(|row| row.get(position.x))
+    }
+
+    pub fn validate(&self) -> Result<(), LevelValidationError> {
+        if self.grid.iter().all(|row| row.is_empty()) {
+            return Err(LevelValidationError::EmptyGrid);
+        }
+        if !self.is_walkable(&self.start_pos, None) {
+            return Err(LevelValidationError::StartNotWalkable(self.start_pos));
+        }
+        if let Some(position) = self
+            .interactables
+            .keys()
+            .find(|position| self.tile(position).is_none())
+        {
+            return Err(LevelValidationError::InteractableOutside(*position));
+        }
+        if let Some(torch) = self
+            .torches
+            .iter()
+            .find(|torch| self.tile(&torch.grid_position).is_none())
+        {
+            return Err(LevelValidationError::TorchOutside(torch.grid_position));
+        }
+        if let Some(emitter) = self
+            .emitters
+            .iter()
+            .find(|emitter| self.tile(&emitter.grid_position).is_none())
+        {
+            return Err(LevelValidationError::EmitterOutside(emitter.grid_position));
+        }
+        if let Some(position) = self
+            .bridges
+            .iter()
+            .flat_map(|bridge| &bridge.cells)
+            .find(|position| !matches!(self.tile(position), Some(Tile::Pit)))
+        {
+            return Err(LevelValidationError::BridgeNotOverPit(*position));
+        }
+        if let Some(plate) = self
+            .pressure_plates
+            .iter()
+            .find(|plate| self.tile(&plate.grid_position).is_none())
+        {
+            return Err(LevelValidationError::PressurePlateOutside(
+                plate.grid_position,
+            ));
+        }
+        if let Some(position) = self
+            .triggers
+            .keys()
+            .find(|position| self.tile(position).is_none())
+        {
+            return Err(LevelValidationError::TriggerOutside(*position));
+        }
+        if let Some(position) = self
+            .traps
+            .iter()
+            .flat_map(|trap| match &trap.kind {
+                TrapKind::Darts { origin, .. } => vec![trap.grid_position, *origin],
+                _ => vec![trap.grid_position],
+            })
+            .find(|position| self.tile(position).is_none())
+        {
+            return Err(LevelValidationError::TrapOutside(position));
+        }
+        if let Some(target) = self
+            .interactables
+            .values()
+            .flat_map(Interactable::targets)
+            .chain(self.pressure_plates.iter().flat_map(|plate| &plate.targets))
+            .chain(self.triggers.values().flat_map(TileTrigger::targets))
+            .find(|target| self.mechanism_active(target, None).is_none())
+        {
+            return Err(LevelValidationError::UnknownMechanism(target.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the mechanism with the id is active, `None` if there is no such mechanism
+    pub fn mechanism_active(&self, id: &str, level_state: Option<&LevelState>) -> Option<bool> {
+        let initial = self
+            .bridges
+            .iter()
+            .find(|bridge| bridge.id == id)
+            .map(|bridge| bridge.extended)
+            .or_else(|| {
+                self.interactables
+                    .iter()
+                    .filter_map(|(position, interactable)| interactable.mechanism(position))
+                    .find(|(mechanism, _)| mechanism == id)
+                    .map(|(_, active)| active)
+            })?;
+
+        Some(
+            level_state
+                .and_then(|level_state| level_state.mechanisms.get(id))
+                .copied()
+                .unwrap_or(initial),
+        )
+    }
+
+    pub fn trap(&self, position: &GridPosition) -> Option<&Trap> {
+        self.traps
+            .iter()
+            .find(|trap| trap.grid_position == *position)
+    }
+
+    /// Whether the floor of the cell gave way, it can not be crossed anymore
+    pub fn is_collapsed(&self, position: &GridPosition, level_state: Option<&LevelState>) -> bool {
+        matches!(
+            self.trap(position).map(|trap| &trap.kind),
+            Some(TrapKind::Collapse { .. })
+        ) && level_state.is_some_and(|level_state| level_state.sprung_traps.contains(position))
+    }
+
+    /// Whether an extended bridge spans the cell
+    fn is_bridged(&self, position: &GridPosition, level_state: Option<&LevelState>) -> bool {
+        self.bridges.iter().any(|bridge| {
+            bridge.cells.contains(position)
+                && self.mechanism_active(&bridge.id, level_state) == Some(true)
+        })
+    }
+
+    /// Whether the interactable on the cell is a door that is open
+    fn is_open_door(&self, position: &GridPosition, level_state: Option<&LevelState>) -> bool {
+        match self.interactables.get(position) {
+            Some(Interactable::Door(door)) => {
+                self.mechanism_active(&door.mechanism_id(position), level_state) == Some(true)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether an actor could stand on the cell, ignoring other actors.
+    /// Without a [`LevelState`] mechanisms are as the level file sets them up.
+    pub fn is_walkable(&self, position: &GridPosition, level_state: Option<&LevelState>) -> bool {
+        let floor = match self.tile(position) {
+            None | Some(Tile::Void) => false,
+            Some(Tile::Pit) => self.is_bridged(position, level_state),
+            Some(Tile::Stone) => !self.is_collapsed(position, level_state),
+        };
+
+        floor
+            && (!self.interactables.contains_key(position)
+                || self.is_open_door(position, level_state))
+    }
+
+    /// Cells outside of the grid, void and closed doors block the line of sight
+    pub fn is_opaque(&self, position: &GridPosition, level_state: Option<&LevelState>) -> bool {
+        matches!(self.tile(position), None | Some(Tile::Void))
+            || (matches!(
+                self.interactables.get(position),
+                Some(Interactable::Door(_))
+            ) && !self.is_open_door(position, level_state))
+    }
 }
 
 #[non_exhaustive]
@@ -29,6 +222,33 @@ pub enum LevelAssetLoaderError {
     /// A [RON](ron) Error
     #[error("Could not parse RON: {0}")]
     RonSpannedError(#[from] ron::error::SpannedError),
+    /// A [Level] that parsed, but does not make sense
+    #[error("Invalid level: {0}")]
+    Invalid(#[from] LevelValidationError),
+}
+
+#[derive(Debug, Error)]
+pub enum LevelValidationError {
+    #[error("The grid is empty")]
+    EmptyGrid,
+    #[error("The start position {0:?} is not walkable")]
+    StartNotWalkable(GridPosition),
+    #[error("The interactable at {0:?} is outside of the grid")]
+    InteractableOutside(GridPosition),
+    #[error("The torch at {0:?} is outside of the grid")]
+    TorchOutside(GridPosition),
+    #[error("The sound emitter at {0:?} is outside of the grid")]
+    EmitterOutside(GridPosition),
+    #[error("The bridge cell {0:?} is not over a pit")]
+    BridgeNotOverPit(GridPosition),
+    #[error("The pressure plate at {0:?} is outside of the grid")]
+    PressurePlateOutside(GridPosition),
+    #[error("The trigger at {0:?} is outside of the grid")]
+    TriggerOutside(GridPosition),
+    #[error("The trap at {0:?} is outside of the grid")]
+    TrapOutside(GridPosition),
+    #[error("No mechanism has the id {0:?}")]
+    UnknownMechanism(String),
 }
 
 #[derive(Default)]
@@ -47,6 +267,7 @@ impl AssetLoader for LevelAssetLoader {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
         let custom_asset = ron::de::from_bytes::<Level>(&bytes)?;
+        custom_asset.validate()?;
         Ok(custom_asset)
     }
 
@@ -1,25 +1,102 @@
-use bevy::{color::palettes::css::YELLOW, prelude::*};
+use bevy::{asset::AssetLoadFailedEvent, color::palettes::css::YELLOW, prelude::*};
 use bevy_flycam::FlyCam;
 
 use crate::{
+    camera::CameraEffects,
+    campaign::{ActiveCampaign, Campaign},
     controls::Controllable,
+    health::Health,
     loading::LevelAssets,
+    loot::LootSeed,
     movement::{GridDirection, GridPosition},
+    save::SavedGame,
+    GameState,
 };
 
-use super::{Level, Player};
+use super::{
+    create::LevelGeometry, error::LevelError, CarriedLight, CurrentLevel, Level, LevelStates,
+    Player, Viewshed, TILE_SIZE,
+};
 
 #[derive(Debug, Event)]
 pub struct ChangeLevel {
     pub level: Handle<Level>,
+    /// Defaults to the start position of the level
     pub position: Option<GridPosition>,
+    /// Defaults to [`GridDirection::default`]
+    pub direction: Option<GridDirection>,
+}
+
+/// Chosen in the menu to start the next game in `level` instead of the entry of the campaign
+#[derive(Debug, Resource)]
+pub struct NewGame {
+    pub level: Handle<Level>,
+    /// Defaults to a random [`LootSeed`]
+    pub seed: Option<LootSeed>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup(
     mut commands: Commands,
     mut change_level_evw: EventWriter<ChangeLevel>,
+    mut level_error_evw: EventWriter<LevelError>,
+    mut next_state: ResMut<NextState<GameState>>,
     level_assets: Res<LevelAssets>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+    new_game: Option<Res<NewGame>>,
+    saved_game: Option<Res<SavedGame>>,
+    time: Res<Time<Real>>,
 ) {
+    let saved_game = saved_game.map(|saved_game| {
+        commands.remove_resource::<SavedGame>();
+        saved_game.clone()
+    });
+    let mut loot_seed = None;
+    let level = match (&saved_game, new_game) {
+        /* A saved game continues where it was left */
+        (Some(saved_game), _) => {
+            loot_seed = Some(saved_game.loot_seed);
+            let Some(level) = level_assets.levels.get(&saved_game.level) else {
+                level_error_evw.send(LevelError::UnknownLevel(saved_game.level.clone()));
+                next_state.set(GameState::Menu);
+                return;
+            };
+            level.clone()
+        }
+        (None, Some(new_game)) => {
+            commands.remove_resource::<NewGame>();
+            loot_seed = new_game.seed;
+            new_game.level.clone()
+        }
+        (None, None) => {
+            let Some(campaign) = campaigns.get(&active_campaign.0) else {
+                level_error_evw.send(LevelError::NoCampaign);
+                next_state.set(GameState::Menu);
+                return;
+            };
+            let Some(level) = level_assets.levels.get(&campaign.entry) else {
+                level_error_evw.send(LevelError::UnknownLevel(campaign.entry.clone()));
+                next_state.set(GameState::Menu);
+                return;
+            };
+            level.clone()
+        }
+    };
+
+    /* Every new game starts with unexplored levels, a saved one with the levels as they were left */
+    commands.insert_resource(
+        saved_game
+            .as_ref()
+            .map(|saved_game| {
+                LevelStates::from_paths(&saved_game.level_states, &level_assets.levels)
+            })
+            .unwrap_or_default(),
+    );
+    commands.insert_resource(loot_seed.unwrap_or_else(|| LootSeed::from_time(&time)));
+    if let Some(saved_game) = &saved_game {
+        commands.insert_resource(saved_game.flags.clone());
+    }
     commands
         .spawn((
             Player,
@@ -28,25 +105,150 @@ pub fn setup(
             Controllable,
             GridPosition::default(),
             GridDirection::default(),
+            saved_game
+                .as_ref()
+                .map(|saved_game| saved_game.fuel)
+                .unwrap_or_default(),
+            Viewshed::new(8),
+            saved_game
+                .as_ref()
+                .and_then(|saved_game| saved_game.health)
+                .unwrap_or(Health::new(10)),
+            saved_game
+                .as_ref()
+                .map(|saved_game| saved_game.inventory.clone())
+                .unwrap_or_default(),
         ))
         .with_children(|parent| {
-            parent.spawn(PointLightBundle {
-                point_light: PointLight {
-                    color: YELLOW.into(),
+            parent.spawn((
+                PointLightBundle {
+                    point_light: PointLight {
+                        color: YELLOW.into(),
+                        intensity: 15_000_000.0,
+                        range: 64.0,
+                        radius: 64.0,
+                        shadows_enabled: true,
+                        ..default()
+                    },
+                    ..default()
+                },
+                CarriedLight {
                     intensity: 15_000_000.0,
                     range: 64.0,
-                    radius: 64.0,
-                    shadows_enabled: true,
-                    ..default()
                 },
-                ..default()
-            });
+            ));
 
-            parent.spawn(Camera3dBundle::default());
+            parent.spawn((
+                Camera3dBundle::default(),
+                CameraEffects::default(),
+                SpatialListener::new(TILE_SIZE / 4.0),
+            ));
         });
 
     change_level_evw.send(ChangeLevel {
-        level: level_assets.levels.get("level/000.lvl").unwrap().clone(),
-        position: None,
+        level,
+        position: saved_game.as_ref().map(|saved_game| saved_game.position),
+        direction: saved_game.as_ref().map(|saved_game| saved_game.direction),
     });
 }
+
+/// Removes the player and the level, when going back to the menu
+pub fn teardown(
+    mut commands: Commands,
+    mut current_level: ResMut<CurrentLevel>,
+    entities: Query<Entity, Or<(With<Player>, With<LevelGeometry>)>>,
+) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    *current_level = CurrentLevel::default();
+}
+
+pub fn report_level_load_errors(
+    mut load_failed_evr: EventReader<AssetLoadFailedEvent<Level>>,
+    mut level_error_evw: EventWriter<LevelError>,
+) {
+    for event in load_failed_evr.read() {
+        level_error_evw.send(LevelError::LoadFailed {
+            path: event.path.to_string(),
+            reason: event.error.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, utils::HashMap};
+
+    use super::*;
+    use crate::{
+        flags::{FlagValue, GameFlags},
+        inventory::Inventory,
+        item::ItemId,
+        level::LightFuel,
+    };
+
+    #[test]
+    fn saved_games_continue_as_they_were_left() {
+        let first: Handle<Level> = Handle::weak_from_u128(1);
+        let second: Handle<Level> = Handle::weak_from_u128(2);
+        let levels = HashMap::from_iter([
+            ("level/000.lvl".to_string(), first.clone()),
+            ("level/001.lvl".to_string(), second.clone()),
+        ]);
+
+        let mut level_states = LevelStates::default();
+        let chest = GridPosition { x: 2, y: 3 };
+        level_states.get_mut(&second).opened_chests.insert(chest);
+        level_states
+            .get_mut(&second)
+            .mechanisms
+            .insert("gate".to_string(), true);
+        let mut inventory = Inventory::default();
+        inventory.add(&ItemId("key".to_string()), 1, 1);
+        let mut flags = GameFlags::default();
+        flags.set("gate_opened", FlagValue::Bool(true));
+
+        let saved_game = SavedGame {
+            level: "level/001.lvl".to_string(),
+            position: GridPosition { x: 1, y: 1 },
+            direction: GridDirection::East,
+            fuel: LightFuel::default(),
+            health: Some(Health {
+                current: 4,
+                max: 10,
+            }),
+            inventory,
+            flags,
+            loot_seed: LootSeed(7),
+            level_states: level_states.by_path(&levels),
+        };
+        let saved_game: SavedGame = ron::from_str(&ron::to_string(&saved_game).unwrap()).unwrap();
+
+        let mut app = App::new();
+        app.add_event::<ChangeLevel>()
+            .add_event::<LevelError>()
+            .init_resource::<NextState<GameState>>()
+            .init_resource::<ActiveCampaign>()
+            .init_resource::<Assets<Campaign>>()
+            .init_resource::<Time<Real>>()
+            .insert_resource(LevelAssets { levels })
+            .insert_resource(saved_game);
+        app.world_mut().run_system_once(setup);
+
+        let world = app.world_mut();
+        let level_states = world.resource::<LevelStates>();
+        assert!(level_states.get(&first).is_none());
+        let level_state = level_states.get(&second).unwrap();
+        assert!(level_state.opened_chests.contains(&chest));
+        assert_eq!(level_state.mechanisms.get("gate"), Some(&true));
+        assert!(world.resource::<GameFlags>().is_set("gate_opened"));
+        assert_eq!(*world.resource::<LootSeed>(), LootSeed(7));
+
+        let (health, inventory) = world
+            .query_filtered::<(&Health, &Inventory), With<Player>>()
+            .single(world);
+        assert_eq!((health.current, health.max), (4, 10));
+        assert_eq!(inventory.count(&ItemId("key".to_string())), 1);
+    }
+}
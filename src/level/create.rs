@@ -1,28 +1,56 @@
 use bevy::prelude::*;
 
 use crate::{
+    audio::EmitterSound,
+    item::Items,
     loading::SceneAssets,
     movement::{GridDirection, GridPosition},
 };
 
-use super::{asset::Level, change::ChangeLevel, CurrentLevel, Player, Tile, TILE_SIZE};
+use super::{
+    asset::Level,
+    change::ChangeLevel,
+    error::LevelError,
+    fov::FogOfWar,
+    interactables::{ChestLoot, Interactable},
+    mechanism::{Mechanism, ShownWhen},
+    state::{LevelState, LevelStates},
+    trap::{TrapKind, TrapMarker},
+    CurrentLevel, Player, Tile, TILE_SIZE,
+};
 
 /// Marker Component so all level specific entities can be despawned of level change
 #[derive(Debug, Default, Component)]
 pub struct LevelGeometry;
 
-fn create_level_geometry(commands: &mut Commands, level: &Level, scene_assets: &SceneAssets) {
+/// Sent after the current level was rebuilt in place, because its file was modified
+#[derive(Debug, Event)]
+pub struct LevelReloaded;
+
+fn create_level_geometry(
+    commands: &mut Commands,
+    level: &Level,
+    level_state: Option<&LevelState>,
+    scene_assets: &SceneAssets,
+    items: &Items,
+) {
     for (y, row) in level.grid.iter().enumerate() {
         for (x, tile) in row.iter().enumerate() {
             match tile {
                 Tile::Void => { /* do nothing */ }
-                _tile => {
-                    /* Ground */
+                tile => {
+                    /* Ground, pits stay open */
                     let translation =
                         Vec3::new(x as f32 * TILE_SIZE, -TILE_SIZE / 2.0, y as f32 * TILE_SIZE);
-                    commands
-                        .spawn(scene_assets.floor_tile(Transform::from_translation(translation)))
-                        .insert(LevelGeometry);
+                    if !matches!(tile, Tile::Pit)
+                        && !level.is_collapsed(&GridPosition { x, y }, level_state)
+                    {
+                        commands
+                            .spawn(
+                                scene_assets.floor_tile(Transform::from_translation(translation)),
+                            )
+                            .insert(LevelGeometry);
+                    }
 
                     /* North Wall */
                     if (y == 0) || matches!(level.grid[y - 1][x], Tile::Void) {
@@ -103,9 +131,118 @@ fn create_level_geometry(commands: &mut Commands, level: &Level, scene_assets: &
     /* Interactables */
     for (k, v) in &level.interactables {
         let transform = Transform::from_translation(k.into());
+        let mut entity = commands.spawn(v.bundle(scene_assets, transform));
+        entity.insert((LevelGeometry, FogOfWar(*k)));
+
+        /* The leaf of a door is a child, so it is fogged together with the doorway */
+        if let Interactable::Door(door) = v {
+            let id = door.mechanism_id(k);
+            let shown_when = ShownWhen(false);
+            let active = level.mechanism_active(&id, level_state) == Some(true);
+            entity.with_children(|parent| {
+                parent.spawn((
+                    SceneBundle {
+                        visibility: shown_when.visibility(active),
+                        ..scene_assets.door_leaf()
+                    },
+                    Mechanism(id),
+                    shown_when,
+                ));
+            });
+        }
+
+        /* Closed chests show the model of the first item that has one */
+        if let Interactable::Chest(chest) = v {
+            let opened =
+                level_state.is_some_and(|level_state| level_state.opened_chests.contains(k));
+            let model = chest
+                .items
+                .iter()
+                .find_map(|loot| items.get(&loot.item)?.model.clone());
+            if let (false, Some(model)) = (opened, model) {
+                entity.with_children(|parent| {
+                    parent.spawn((
+                        SceneBundle {
+                            scene: model,
+                            transform: Transform::from_xyz(0.0, 1.2, 0.5),
+                            ..default()
+                        },
+                        ChestLoot(*k),
+                    ));
+                });
+            }
+        }
+    }
+
+    /* Traps, collapsing floors look like any other floor */
+    for trap in &level.traps {
+        if matches!(trap.kind, TrapKind::Collapse { .. }) {
+            continue;
+        }
+        let position = trap.grid_position;
+        let shown = level_state.is_some_and(|level_state| {
+            level_state.detected_traps.contains(&position)
+                && !level_state.disarmed_traps.contains(&position)
+        });
+        commands.spawn((
+            SceneBundle {
+                visibility: if shown {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..scene_assets.trap(Transform::from_translation((&position).into()))
+            },
+            TrapMarker(position),
+            LevelGeometry,
+        ));
+    }
+
+    /* Mechanisms */
+    for plate in &level.pressure_plates {
+        let transform = Transform::from_translation((&plate.grid_position).into());
+        commands
+            .spawn(scene_assets.pressure_plate(transform))
+            .insert((LevelGeometry, FogOfWar(plate.grid_position)));
+    }
+    for bridge in &level.bridges {
+        let shown_when = ShownWhen(true);
+        let active = level.mechanism_active(&bridge.id, level_state) == Some(true);
+        for cell in &bridge.cells {
+            let translation = Vec3::from(cell) + Vec3::new(0.0, -TILE_SIZE / 2.0, 0.0);
+            commands.spawn((
+                SceneBundle {
+                    visibility: shown_when.visibility(active),
+                    ..scene_assets.floor_tile(Transform::from_translation(translation))
+                },
+                Mechanism(bridge.id.clone()),
+                shown_when,
+                LevelGeometry,
+            ));
+        }
+    }
+
+    /* Torches */
+    for torch in &level.torches {
         commands
-            .spawn(v.bundle(scene_assets, transform))
+            .spawn(torch.bundle(scene_assets))
             .insert(LevelGeometry);
+        commands
+            .spawn(torch.light_bundle())
+            .insert((LevelGeometry, EmitterSound::Torch));
+    }
+
+    /* Sounds */
+    for emitter in &level.emitters {
+        commands.spawn(emitter.bundle()).insert(LevelGeometry);
+    }
+
+    /* Items scripts left on the ground */
+    for ground_item in level_state
+        .iter()
+        .flat_map(|level_state| &level_state.ground_items)
+    {
+        ground_item.spawn(commands, items);
     }
 }
 
@@ -115,32 +252,93 @@ fn despawn_level_geometry(commands: &mut Commands, entities: &Query<Entity, With
     }
 }
 
+/// Changes to levels that are not loaded are ignored, so the player stays in the current level
 pub fn level_change_despawn(
     mut commands: Commands,
     mut change_level_evr: EventReader<ChangeLevel>,
+    level_assets: Res<Assets<Level>>,
     level_entities: Query<Entity, With<LevelGeometry>>,
 ) {
-    for _ in change_level_evr.read() {
+    for event in change_level_evr.read() {
+        if !level_assets.contains(&event.level) {
+            continue;
+        }
+
         info!("Despawning...");
         despawn_level_geometry(&mut commands, &level_entities);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn level_change_create(
     mut commands: Commands,
     mut change_level_evr: EventReader<ChangeLevel>,
     mut current_level: ResMut<CurrentLevel>,
     level_assets: Res<Assets<Level>>,
+    level_states: Res<LevelStates>,
     scene_assets: Res<SceneAssets>,
+    items: Items,
+    mut level_error_evw: EventWriter<LevelError>,
 ) {
     for event in change_level_evr.read() {
+        let Some(level) = level_assets.get(&event.level) else {
+            let path = event.level.path().map(ToString::to_string);
+            level_error_evw.send(LevelError::NotLoaded(path.unwrap_or_default()));
+            continue;
+        };
+
         info!("Creating...");
         current_level.0 = event.level.clone();
 
-        let level = level_assets.get(&event.level).unwrap();
+        create_level_geometry(
+            &mut commands,
+            level,
+            level_states.get(&event.level),
+            &scene_assets,
+            &items,
+        );
+    }
+}
 
-        create_level_geometry(&mut commands, level, &scene_assets);
+/// Rebuilds the current level in place, whenever its file was modified.
+/// Unlike a [`ChangeLevel`], scripts, ambience and the campaign do not notice it
+#[allow(clippy::too_many_arguments)]
+pub fn reload_current_level(
+    mut commands: Commands,
+    mut asset_evr: EventReader<AssetEvent<Level>>,
+    mut level_reloaded_evw: EventWriter<LevelReloaded>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    level_states: Res<LevelStates>,
+    scene_assets: Res<SceneAssets>,
+    items: Items,
+    level_entities: Query<Entity, With<LevelGeometry>>,
+    mut players: Query<&mut GridPosition, With<Player>>,
+) {
+    /* Saving a file can modify it more than once, it is rebuilt only once */
+    let modified = asset_evr
+        .read()
+        .filter(|event| event.is_modified(&current_level.0))
+        .count();
+    if modified == 0 {
+        return;
     }
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    info!("Reloading level...");
+    let level_state = level_states.get(&current_level.0);
+    despawn_level_geometry(&mut commands, &level_entities);
+    create_level_geometry(&mut commands, level, level_state, &scene_assets, &items);
+
+    for mut grid_position in &mut players {
+        /* Only move the player, if their position became invalid */
+        if !level.is_walkable(&grid_position, level_state) {
+            *grid_position = level.start_pos;
+        }
+    }
+    level_reloaded_evw.send(LevelReloaded);
 }
 
 pub fn move_player_to_start_pos(
@@ -149,19 +347,16 @@ pub fn move_player_to_start_pos(
     mut player_pos: Query<(&mut GridPosition, &mut GridDirection), With<Player>>,
 ) {
     for event in change_level_evr.read() {
-        info!("Moving player to start...");
-
-        let new_position = match event.position {
-            Some(position) => position,
-            None => {
-                let level = level_assets.get(&event.level).unwrap();
-                level.start_pos
-            }
+        let Some(level) = level_assets.get(&event.level) else {
+            continue;
         };
 
+        info!("Moving player to start...");
+        let new_position = event.position.unwrap_or(level.start_pos);
+
         for (mut grid_position, mut grid_direction) in &mut player_pos {
             *grid_position = new_position;
-            *grid_direction = GridDirection::default();
+            *grid_direction = event.direction.unwrap_or_default();
         }
     }
 }
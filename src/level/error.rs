@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::toast::Toast;
+
+/// Problems with levels, which are reported instead of crashing the game
+#[derive(Debug, Error, Event)]
+pub enum LevelError {
+    #[error("No campaign is selected")]
+    NoCampaign,
+    #[error("There is no level named \"{0}\"")]
+    UnknownLevel(String),
+    #[error("The level {0} is not loaded")]
+    NotLoaded(String),
+    #[error("Failed to load level {path}: {reason}")]
+    LoadFailed { path: String, reason: String },
+    #[error("There is no dialogue named \"{0}\"")]
+    UnknownDialogue(String),
+    #[error("There is no item named \"{0}\"")]
+    UnknownItem(String),
+    #[error("There is no loot table named \"{0}\"")]
+    UnknownLootTable(String),
+    #[error("The loot table {0} is nested too deep, it may contain itself")]
+    LootTableTooDeep(String),
+    #[error("There is no script named \"{0}\"")]
+    UnknownScript(String),
+    #[error("Script error in {location}: {reason}")]
+    ScriptFailed { location: String, reason: String },
+}
+
+pub fn report_level_errors(
+    mut level_error_evr: EventReader<LevelError>,
+    mut toast_evw: EventWriter<Toast>,
+) {
+    for level_error in level_error_evr.read() {
+        error!("{level_error}");
+        toast_evw.send(Toast(level_error.to_string()));
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    movement::{GridDirection, GridPosition},
+    GameState,
+};
+
+use super::{
+    asset::Level,
+    create::LevelReloaded,
+    mechanism::MechanismChanged,
+    state::{LevelState, LevelStates},
+    CurrentLevel, LightFuel, Player,
+};
+
+pub struct FovPlugin;
+
+impl Plugin for FovPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (update_viewsheds, mark_explored, fog_of_war)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Cells an entity can currently see, updated whenever it moves or turns
+#[derive(Debug, Component)]
+pub struct Viewshed {
+    pub radius: usize,
+    pub visible: HashSet<GridPosition>,
+}
+
+impl Viewshed {
+    pub fn new(radius: usize) -> Self {
+        Self {
+            radius,
+            visible: HashSet::default(),
+        }
+    }
+}
+
+/// Hides the entity until the cell at the position was seen by the player
+#[derive(Debug, Component)]
+pub struct FogOfWar(pub GridPosition);
+
+/// Whether there is nothing opaque on the line between the cells `from` and `to`.
+/// The cells themselves may be opaque, so walls can be seen.
+pub fn line_of_sight(
+    level: &Level,
+    level_state: Option<&LevelState>,
+    from: &GridPosition,
+    to: &GridPosition,
+) -> bool {
+    /* A cell can always see itself, the steps below would walk off it */
+    if from == to {
+        return true;
+    }
+
+    /* Bresenham */
+    let (mut x, mut y) = (from.x as i64, from.y as i64);
+    let (to_x, to_y) = (to.x as i64, to.y as i64);
+    let dx = (to_x - x).abs();
+    let dy = -(to_y - y).abs();
+    let step_x = if x < to_x { 1 } else { -1 };
+    let step_y = if y < to_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    loop {
+        let double_error = 2 * error;
+        if double_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+
+        if x == to_x && y == to_y {
+            return true;
+        }
+
+        let position = GridPosition {
+            x: x as usize,
+            y: y as usize,
+        };
+        if level.is_opaque(&position, level_state) {
+            return false;
+        }
+    }
+}
+
+/// All cells within `radius` in front of `origin` looking in `direction` that are in line of sight
+pub fn field_of_view(
+    level: &Level,
+    level_state: Option<&LevelState>,
+    origin: &GridPosition,
+    direction: &GridDirection,
+    radius: usize,
+) -> HashSet<GridPosition> {
+    let facing = Vec3::from(direction);
+    let mut visible = HashSet::default();
+    visible.insert(*origin);
+
+    for y in origin.y.saturating_sub(radius)..=origin.y + radius {
+        for x in origin.x.saturating_sub(radius)..=origin.x + radius {
+            let position = GridPosition { x, y };
+            if position == *origin || level.tile(&position).is_none() {
+                continue;
+            }
+
+            let delta = Vec3::new(x as f32 - origin.x as f32, 0.0, y as f32 - origin.y as f32);
+            if delta.length() > radius as f32 || delta.dot(facing) < 0.0 {
+                continue;
+            }
+
+            if line_of_sight(level, level_state, origin, &position) {
+                visible.insert(position);
+            }
+        }
+    }
+
+    visible
+}
+
+fn update_viewsheds(
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    level_states: Res<LevelStates>,
+    mut mechanism_changed_evr: EventReader<MechanismChanged>,
+    mut level_reloaded_evr: EventReader<LevelReloaded>,
+    mut viewsheds: Query<(
+        Ref<GridPosition>,
+        Ref<GridDirection>,
+        &mut Viewshed,
+        Option<&LightFuel>,
+    )>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+    let level_state = level_states.get(&current_level.0);
+    /* Opening or closing a door, or rebuilding the level changes what can be seen */
+    let mechanism_changed = mechanism_changed_evr.read().count() > 0;
+    let level_reloaded = level_reloaded_evr.read().count() > 0;
+
+    for (grid_position, direction, mut viewshed, fuel) in &mut viewsheds {
+        if !current_level.is_changed()
+            && !grid_position.is_changed()
+            && !direction.is_changed()
+            && !mechanism_changed
+            && !level_reloaded
+        {
+            continue;
+        }
+
+        /* Without light there is not much to see */
+        let radius = match fuel {
+            Some(fuel) => ((viewshed.radius as f32 * fuel.factor().sqrt()).round() as usize).max(1),
+            None => viewshed.radius,
+        };
+        viewshed.visible = field_of_view(level, level_state, &grid_position, &direction, radius);
+    }
+}
+
+fn mark_explored(
+    current_level: Res<CurrentLevel>,
+    mut level_states: ResMut<LevelStates>,
+    viewsheds: Query<&Viewshed, (With<Player>, Changed<Viewshed>)>,
+) {
+    for viewshed in &viewsheds {
+        level_states
+            .get_mut(&current_level.0)
+            .explored
+            .extend(viewshed.visible.iter().copied());
+    }
+}
+
+fn fog_of_war(
+    current_level: Res<CurrentLevel>,
+    level_states: Res<LevelStates>,
+    mut fogged: Query<(&FogOfWar, &mut Visibility)>,
+) {
+    let explored = level_states
+        .get(&current_level.0)
+        .map(|level_state| &level_state.explored);
+
+    for (fog_of_war, mut visibility) in &mut fogged {
+        let seen = explored.is_some_and(|explored| explored.contains(&fog_of_war.0));
+        let new_visibility = if seen {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(new_visibility);
+    }
+}
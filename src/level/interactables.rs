@@ -1,20 +1,38 @@
 use bevy::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    condition::Condition,
+    dialogue::Npc,
+    flags::GameFlags,
+    inventory::Inventory,
+    item::{ItemId, PickUp},
+    journal::Readable,
     loading::{LevelAssets, SceneAssets},
-    movement::GridPosition,
+    loot::LootTables,
+    message_log::{LogMessage, MessageCategory},
+    movement::{GridDirection, GridPosition, Teleport},
     GameState,
 };
 
-use super::change::ChangeLevel;
+use super::{
+    asset::Level,
+    change::ChangeLevel,
+    error::LevelError,
+    mechanism::{Signal, SignalAction},
+    CurrentLevel, LevelStates,
+};
 
 pub struct InteractablePlugin;
 
 impl Plugin for InteractablePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<Interact>()
-            .add_systems(Update, interact.run_if(in_state(GameState::Playing)));
+        app.add_event::<Interact>().add_systems(
+            Update,
+            (open_chests, use_teleporters, interact)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
@@ -22,12 +40,17 @@ impl Plugin for InteractablePlugin {
 pub struct Interact {
     pub source: Entity,
     pub target: Interactable,
+    /// Cell of the interactable
+    pub position: GridPosition,
 }
 
 #[derive(Debug, Component, Deserialize, Clone)]
 pub enum Interactable {
-    Chest(Loot),
-    Door,
+    Chest(Chest),
+    Door(Door),
+    Npc(Npc),
+    Readable(Readable),
+    Switch(Switch),
     Teleporter(Teleporter),
 }
 
@@ -35,15 +58,105 @@ impl Interactable {
     pub fn bundle(&self, scene_assets: &SceneAssets, transform: Transform) -> SceneBundle {
         match self {
             Interactable::Chest(_) => scene_assets.chest(transform),
-            Interactable::Door => todo!(),
+            Interactable::Door(door) => {
+                scene_assets.doorway(transform.looking_to(door.facing, Vec3::Y))
+            }
+            Interactable::Npc(_) => scene_assets.npc(transform),
+            Interactable::Readable(readable) => readable.bundle(scene_assets, transform),
+            Interactable::Switch(_) => scene_assets.lever(transform),
             Interactable::Teleporter(_) => scene_assets.pillar(transform),
         }
     }
+
+    /// The id of the mechanism the interactable is part of and whether it starts out active
+    pub fn mechanism(&self, position: &GridPosition) -> Option<(String, bool)> {
+        match self {
+            Interactable::Door(door) => Some((door.mechanism_id(position), door.open)),
+            Interactable::Teleporter(teleporter) => {
+                teleporter.id.clone().map(|id| (id, !teleporter.dormant))
+            }
+            Interactable::Chest(_)
+            | Interactable::Npc(_)
+            | Interactable::Readable(_)
+            | Interactable::Switch(_) => None,
+        }
+    }
+
+    /// The ids of the mechanisms the interactable switches
+    pub fn targets(&self) -> &[String] {
+        match self {
+            Interactable::Switch(switch) => &switch.targets,
+            _ => &[],
+        }
+    }
+}
+
+/// The model of the item a chest holds, until it is opened
+#[derive(Debug, Component)]
+pub struct ChestLoot(pub GridPosition);
+
+/// Holds the items and a roll of the table, the table is rolled when the chest is opened
+#[derive(Debug, Deserialize, Clone)]
+pub struct Chest {
+    #[serde(default)]
+    pub items: Vec<Loot>,
+    /// Path of a loot table, e.g. `loot/common.loot`
+    #[serde(default)]
+    table: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
-pub enum Loot {
-    Key,
+/// Items in a chest or on the ground, by their id in the item database
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Loot {
+    pub item: ItemId,
+    #[serde(default = "Loot::default_count")]
+    pub count: u32,
+}
+
+impl Loot {
+    fn default_count() -> u32 {
+        1
+    }
+}
+
+/// Doors with an id are opened by mechanisms, the others by hand
+#[derive(Debug, Deserialize, Clone)]
+pub struct Door {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    open: bool,
+    /// The side the doorway faces, the door can be passed from the front and the back
+    #[serde(default)]
+    pub facing: GridDirection,
+    /// Doors opened by hand stay locked, unless the condition is met
+    #[serde(default)]
+    requires: Option<Condition>,
+}
+
+impl Door {
+    pub fn mechanism_id(&self, position: &GridPosition) -> String {
+        self.id
+            .clone()
+            .unwrap_or_else(|| format!("door_{}_{}", position.x, position.y))
+    }
+}
+
+/// Sends the action to all of its targets when pulled, shown as a lever
+#[derive(Debug, Deserialize, Clone)]
+pub struct Switch {
+    targets: Vec<String>,
+    #[serde(default = "Switch::default_action")]
+    action: SignalAction,
+    /// The switch does not move, unless the condition is met
+    #[serde(default)]
+    requires: Option<Condition>,
+}
+
+impl Switch {
+    fn default_action() -> SignalAction {
+        SignalAction::Toggle
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -51,34 +164,230 @@ pub struct Teleporter {
     grid_position: GridPosition,
     #[serde(default)]
     level: Option<String>,
+    /// Teleporters with an id can be switched on and off by mechanisms
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    dormant: bool,
 }
 
+/// Switches and doors opened by hand only send [`Signal`]s, the mechanism plugin does the rest.
+/// The other interactables are handled by their own systems
 pub fn interact(
+    mut interact_evr: EventReader<Interact>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut signal_evw: EventWriter<Signal>,
+    inventories: Query<&Inventory>,
+    flags: Res<GameFlags>,
+) {
+    for event in interact_evr.read() {
+        let is_met = |requires: &Option<Condition>| {
+            requires.as_ref().is_none_or(|condition| {
+                condition.is_met(&flags, inventories.get(event.source).ok())
+            })
+        };
+
+        let (requires, targets, action, (refused, used)) = match &event.target {
+            Interactable::Door(door) => {
+                if door.id.is_some() {
+                    log_message_evw.send(LogMessage::new(
+                        MessageCategory::Interaction,
+                        "The door does not move, a mechanism must control it",
+                    ));
+                    continue;
+                }
+                (
+                    &door.requires,
+                    vec![door.mechanism_id(&event.position)],
+                    SignalAction::Toggle,
+                    ("The door is locked", "The door creaks on its hinges"),
+                )
+            }
+            Interactable::Switch(switch) => (
+                &switch.requires,
+                switch.targets.clone(),
+                switch.action,
+                ("The lever does not budge", "You pull the lever"),
+            ),
+            Interactable::Chest(_)
+            | Interactable::Npc(_)
+            | Interactable::Readable(_)
+            | Interactable::Teleporter(_) => continue,
+        };
+
+        if !is_met(requires) {
+            log_message_evw.send(LogMessage::new(MessageCategory::Interaction, refused));
+            continue;
+        }
+        log_message_evw.send(LogMessage::new(MessageCategory::Interaction, used));
+        signal_evw.send_batch(targets.into_iter().map(|target| Signal { target, action }));
+    }
+}
+
+/// Chests are only opened once, their table is rolled when they are
+#[allow(clippy::too_many_arguments)]
+pub fn open_chests(
+    mut commands: Commands,
+    mut interact_evr: EventReader<Interact>,
+    mut level_error_evw: EventWriter<LevelError>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut pick_up_evw: EventWriter<PickUp>,
+    current_level: Res<CurrentLevel>,
+    mut level_states: ResMut<LevelStates>,
+    chest_loots: Query<(Entity, &ChestLoot)>,
+    loot_tables: LootTables,
+) {
+    for event in interact_evr.read() {
+        let Interactable::Chest(chest) = &event.target else {
+            continue;
+        };
+
+        let level_state = level_states.get_mut(&current_level.0);
+        if !level_state.opened_chests.insert(event.position) {
+            log_message_evw.send(LogMessage::new(
+                MessageCategory::Interaction,
+                "The chest is empty",
+            ));
+            continue;
+        }
+
+        /* Seeded by the chest's place, so the order chests are opened in does not change their loot */
+        let mut rolled = Vec::new();
+        if let Some(table) = &chest.table {
+            let level = current_level.path().map(ToString::to_string);
+            match loot_tables.roll_at(table, &level.unwrap_or_default(), event.position) {
+                Ok(loot) => rolled = loot,
+                Err(error) => {
+                    level_error_evw.send(error);
+                }
+            }
+        }
+
+        log_message_evw.send(LogMessage::new(
+            MessageCategory::Interaction,
+            "You open the chest",
+        ));
+        pick_up_evw.send_batch(chest.items.iter().chain(&rolled).map(|loot| PickUp {
+            entity: event.source,
+            item: loot.item.clone(),
+            count: loot.count,
+        }));
+        for (entity, chest_loot) in &chest_loots {
+            if chest_loot.0 == event.position {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn use_teleporters(
     mut interact_evr: EventReader<Interact>,
     mut change_level_evw: EventWriter<ChangeLevel>,
+    mut level_error_evw: EventWriter<LevelError>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut teleport_evw: EventWriter<Teleport>,
     level_assets: Res<LevelAssets>,
-    mut positions: Query<&mut GridPosition>,
+    levels: Res<Assets<Level>>,
+    current_level: Res<CurrentLevel>,
+    level_states: Res<LevelStates>,
 ) {
     for event in interact_evr.read() {
-        match &event.target {
-            Interactable::Chest(loot) => info!("Interact with Chest to get loot: {:?}", loot),
-            Interactable::Door => todo!(),
-            Interactable::Teleporter(teleporter) => {
-                info!("Interact with Teleporter{:?}", teleporter);
-                match &teleporter.level {
-                    Some(level_name) => {
-                        change_level_evw.send(ChangeLevel {
-                            level: level_assets.levels.get(level_name).unwrap().clone(),
-                            position: Some(teleporter.grid_position),
-                        });
-                    }
-                    None => {
-                        if let Ok(mut position) = positions.get_mut(event.source) {
-                            *position = teleporter.grid_position;
-                        }
-                    }
+        let Interactable::Teleporter(teleporter) = &event.target else {
+            continue;
+        };
+
+        debug!("Interact with Teleporter{:?}", teleporter);
+        let active = teleporter.id.as_ref().is_none_or(|id| {
+            levels.get(&current_level.0).is_some_and(|level| {
+                level.mechanism_active(id, level_states.get(&current_level.0)) == Some(true)
+            })
+        });
+        if !active {
+            log_message_evw.send(LogMessage::new(
+                MessageCategory::Interaction,
+                "The teleporter is dormant",
+            ));
+            continue;
+        }
+        log_message_evw.send(LogMessage::new(
+            MessageCategory::Interaction,
+            "The teleporter hums and the world shifts",
+        ));
+        match &teleporter.level {
+            Some(level_name) => match level_assets.levels.get(level_name) {
+                Some(level) => {
+                    change_level_evw.send(ChangeLevel {
+                        level: level.clone(),
+                        position: Some(teleporter.grid_position),
+                        direction: None,
+                    });
                 }
+                None => {
+                    level_error_evw.send(LevelError::UnknownLevel(level_name.clone()));
+                }
+            },
+            None => {
+                teleport_evw.send(Teleport {
+                    entity: event.source,
+                    position: teleporter.grid_position,
+                });
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::FlagValue;
+
+    const SWITCH: &str = r#"Switch((
+        targets: ["bridge", "gate"],
+        action: Activate,
+        requires: Some("Flag(\"power\")"),
+    ))"#;
+
+    /// Pulls the switch once and returns the signals it sent
+    fn pull(app: &mut App) -> Vec<Signal> {
+        let target: Interactable = ron::from_str(SWITCH).unwrap();
+        let source = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(Interact {
+            source,
+            target,
+            position: GridPosition { x: 1, y: 1 },
+        });
+        app.update();
+
+        let mut signal_evr = app.world().resource::<Events<Signal>>().get_reader();
+        signal_evr
+            .read(app.world().resource::<Events<Signal>>())
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn switches_signal_all_targets_once_unlocked() {
+        let mut app = App::new();
+        app.add_event::<Interact>()
+            .add_event::<LogMessage>()
+            .add_event::<Signal>()
+            .init_resource::<GameFlags>()
+            .add_systems(Update, interact);
+
+        assert!(pull(&mut app).is_empty());
+
+        app.world_mut()
+            .resource_mut::<GameFlags>()
+            .set("power", FlagValue::Bool(true));
+        let signals = pull(&mut app);
+        let targets: Vec<_> = signals
+            .iter()
+            .map(|signal| signal.target.as_str())
+            .collect();
+        assert_eq!(targets, ["bridge", "gate"]);
+        assert!(signals
+            .iter()
+            .all(|signal| signal.action == SignalAction::Activate));
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::{color::palettes::css::ORANGE, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    loading::SceneAssets,
+    movement::{GridDirection, GridPosition, TurnTaken},
+    GameState,
+};
+
+use super::{asset::Level, create::LevelReloaded, CurrentLevel, TILE_SIZE};
+
+pub struct LightPlugin;
+
+impl Plugin for LightPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<LightFuel>().add_systems(
+            Update,
+            (flicker, (drain_fuel, update_carried_light).chain())
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A torch mounted on the wall face `direction` of the cell at `grid_position`
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct Torch {
+    pub grid_position: GridPosition,
+    pub direction: GridDirection,
+}
+
+impl Torch {
+    const INTENSITY: f32 = 8_000_000.0;
+
+    /// Position of the wall face the torch is mounted on
+    fn wall_translation(&self) -> Vec3 {
+        Vec3::from(&self.grid_position) + Vec3::from(self.direction) * (TILE_SIZE / 2.0)
+    }
+
+    pub fn bundle(&self, scene_assets: &SceneAssets) -> SceneBundle {
+        scene_assets.torch(
+            Transform::from_translation(self.wall_translation())
+                .looking_to(self.direction, Vec3::Y),
+        )
+    }
+
+    /// The light is not a child of the scene, so it is not affected by the scale of the model
+    pub fn light_bundle(&self) -> (PointLightBundle, Flicker) {
+        let translation =
+            self.wall_translation() - Vec3::from(self.direction) * 4.0 + Vec3::new(0.0, 4.0, 0.0);
+
+        (
+            PointLightBundle {
+                point_light: PointLight {
+                    color: ORANGE.into(),
+                    intensity: Self::INTENSITY,
+                    range: 96.0,
+                    radius: 2.0,
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Flicker {
+                intensity: Self::INTENSITY,
+                /* Offset every torch a bit, so they do not flicker in unison */
+                phase: self.grid_position.x as f32 * 1.7 + self.grid_position.y as f32 * 3.1,
+            },
+        )
+    }
+}
+
+/// Animates the intensity of a [`PointLight`] around `intensity`
+#[derive(Debug, Component)]
+pub struct Flicker {
+    pub intensity: f32,
+    pub phase: f32,
+}
+
+fn flicker(time: Res<Time>, mut lights: Query<(&mut PointLight, &Flicker)>) {
+    let t = time.elapsed_seconds();
+    for (mut light, flicker) in &mut lights {
+        let noise =
+            0.1 * (t * 11.0 + flicker.phase).sin() + 0.05 * (t * 23.7 + flicker.phase * 2.3).sin();
+        light.intensity = flicker.intensity * (1.0 + noise);
+    }
+}
+
+/// The light the player carries around, holding its undimmed values
+#[derive(Debug, Component)]
+pub struct CarriedLight {
+    pub intensity: f32,
+    pub range: f32,
+}
+
+/// Fuel of the light carried by an entity, which drains with every turn taken
+#[derive(Debug, Component, Clone, Copy, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct LightFuel {
+    pub current: f32,
+    pub max: f32,
+    /// Fuel consumed per turn
+    pub drain: f32,
+}
+
+impl Default for LightFuel {
+    fn default() -> Self {
+        Self {
+            current: 300.0,
+            max: 300.0,
+            drain: 1.0,
+        }
+    }
+}
+
+impl LightFuel {
+    /// Below this fraction of fuel the light starts to shrink
+    const LOW: f32 = 0.25;
+    /// Even without any fuel left there is still some glow
+    const MIN_FACTOR: f32 = 0.1;
+
+    pub fn refill(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max > 0.0 {
+            (self.current / self.max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Factor that is applied to the `intensity` and `range` of the carried light
+    pub fn factor(&self) -> f32 {
+        (self.fraction() / Self::LOW).clamp(Self::MIN_FACTOR, 1.0)
+    }
+}
+
+fn drain_fuel(mut turn_taken_evr: EventReader<TurnTaken>, mut fuels: Query<&mut LightFuel>) {
+    for event in turn_taken_evr.read() {
+        if let Ok(mut fuel) = fuels.get_mut(event.entity) {
+            fuel.current = (fuel.current - fuel.drain).max(0.0);
+        }
+    }
+}
+
+fn update_carried_light(
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut level_reloaded_evr: EventReader<LevelReloaded>,
+    fuels: Query<Ref<LightFuel>>,
+    mut lights: Query<(&Parent, &mut PointLight, &CarriedLight)>,
+) {
+    let level_changed = level_reloaded_evr.read().count() > 0 || current_level.is_changed();
+    let level_factor = level_assets
+        .get(&current_level.0)
+        .and_then(|level| level.carried_light)
+        .unwrap_or(1.0);
+
+    for (parent, mut light, carried_light) in &mut lights {
+        let fuel = fuels.get(parent.get()).ok();
+        if !level_changed && !fuel.as_ref().is_some_and(|fuel| fuel.is_changed()) {
+            continue;
+        }
+
+        let fuel_factor = fuel.map_or(1.0, |fuel| fuel.factor());
+        light.intensity = carried_light.intensity * level_factor * fuel_factor;
+        light.range = carried_light.range * level_factor.sqrt() * fuel_factor.sqrt();
+    }
+}
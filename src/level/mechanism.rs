@@ -0,0 +1,188 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+use crate::{
+    message_log::{LogMessage, MessageCategory},
+    movement::GridPosition,
+    GameState,
+};
+
+use super::{asset::Level, state::LevelStates, CurrentLevel};
+
+pub struct MechanismPlugin;
+
+/// This plugin routes [`Signal`]s from switches and pressure plates to the mechanisms they are wired to
+/// New mechanisms only need an id in the level file and a system reacting to [`MechanismChanged`]
+impl Plugin for MechanismPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Signal>()
+            .add_event::<MechanismChanged>()
+            .add_systems(
+                Update,
+                (press_plates, apply_signals, update_mechanism_visibility)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Switches the mechanism with the id `target` in the current level
+#[derive(Debug, Clone, Event)]
+pub struct Signal {
+    pub target: String,
+    pub action: SignalAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SignalAction {
+    Toggle,
+    Activate,
+    Deactivate,
+}
+
+impl SignalAction {
+    fn apply(&self, active: bool) -> bool {
+        match self {
+            SignalAction::Toggle => !active,
+            SignalAction::Activate => true,
+            SignalAction::Deactivate => false,
+        }
+    }
+}
+
+/// Sent whenever a mechanism of the current level was switched
+#[derive(Debug, Clone, Event)]
+pub struct MechanismChanged {
+    pub id: String,
+    pub active: bool,
+}
+
+/// Links an entity to the mechanism with the id, e.g. the door or bridge it shows
+#[derive(Debug, Component)]
+pub struct Mechanism(pub String);
+
+/// Shows the [`Mechanism`] entity only while the mechanism is (`true`) or is not (`false`) active,
+/// e.g. the tiles of an extended bridge or the leaf of a closed door
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ShownWhen(pub bool);
+
+impl ShownWhen {
+    pub fn visibility(&self, active: bool) -> Visibility {
+        if self.0 == active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+}
+
+/// Walkable tiles over pits, which are only there while the mechanism `id` is active
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bridge {
+    pub id: String,
+    pub cells: Vec<GridPosition>,
+    #[serde(default)]
+    pub extended: bool,
+}
+
+/// Activates its targets while something stands on it and deactivates them when left
+#[derive(Debug, Clone, Deserialize)]
+pub struct PressurePlate {
+    pub grid_position: GridPosition,
+    pub targets: Vec<String>,
+    /// Latching plates stay pressed once they were stepped on
+    #[serde(default)]
+    pub latch: bool,
+}
+
+fn press_plates(
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut level_states: ResMut<LevelStates>,
+    moved: Query<(), Changed<GridPosition>>,
+    positions: Query<&GridPosition>,
+    mut signal_evw: EventWriter<Signal>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    if moved.is_empty() {
+        return;
+    }
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    let occupied = positions.iter().copied().collect::<HashSet<_>>();
+    let level_state = level_states.get_mut(&current_level.0);
+
+    for plate in &level.pressure_plates {
+        let pressed = occupied.contains(&plate.grid_position);
+        let was_pressed = level_state.pressed_plates.contains(&plate.grid_position);
+        if pressed == was_pressed || (was_pressed && plate.latch) {
+            continue;
+        }
+
+        let action = if pressed {
+            level_state.pressed_plates.insert(plate.grid_position);
+            log_message_evw.send(LogMessage::new(
+                MessageCategory::Interaction,
+                "A pressure plate clicks",
+            ));
+            SignalAction::Activate
+        } else {
+            level_state.pressed_plates.remove(&plate.grid_position);
+            SignalAction::Deactivate
+        };
+        for target in &plate.targets {
+            signal_evw.send(Signal {
+                target: target.clone(),
+                action,
+            });
+        }
+    }
+}
+
+fn apply_signals(
+    mut signal_evr: EventReader<Signal>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut level_states: ResMut<LevelStates>,
+    mut mechanism_changed_evw: EventWriter<MechanismChanged>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for signal in signal_evr.read() {
+        let level_state = level_states.get_mut(&current_level.0);
+        let Some(active) = level.mechanism_active(&signal.target, Some(level_state)) else {
+            warn!("Signal to unknown mechanism {}", signal.target);
+            continue;
+        };
+
+        let new_active = signal.action.apply(active);
+        if new_active == active {
+            continue;
+        }
+
+        level_state
+            .mechanisms
+            .insert(signal.target.clone(), new_active);
+        mechanism_changed_evw.send(MechanismChanged {
+            id: signal.target.clone(),
+            active: new_active,
+        });
+    }
+}
+
+fn update_mechanism_visibility(
+    mut mechanism_changed_evr: EventReader<MechanismChanged>,
+    mut entities: Query<(&Mechanism, &ShownWhen, &mut Visibility)>,
+) {
+    for event in mechanism_changed_evr.read() {
+        for (mechanism, shown_when, mut visibility) in &mut entities {
+            if mechanism.0 == event.id {
+                *visibility = shown_when.visibility(event.active);
+            }
+        }
+    }
+}
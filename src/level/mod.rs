@@ -1,7 +1,16 @@
 mod asset;
 mod change;
 mod create;
+mod error;
+mod fov;
 mod interactables;
+mod light;
+mod mechanism;
+mod path;
+mod script;
+mod state;
+mod trap;
+mod trigger;
 
 use bevy::prelude::*;
 use serde::Deserialize;
@@ -10,13 +19,32 @@ use crate::GameState;
 
 use self::{
     asset::LevelAssetLoader,
-    change::{setup, ChangeLevel},
-    create::{level_change_create, level_change_despawn, move_player_to_start_pos},
+    change::{report_level_load_errors, setup, teardown, ChangeLevel},
+    create::{
+        level_change_create, level_change_despawn, move_player_to_start_pos, reload_current_level,
+        LevelReloaded,
+    },
+    error::report_level_errors,
+    fov::FovPlugin,
     interactables::{interact, InteractablePlugin},
+    light::LightPlugin,
+    mechanism::MechanismPlugin,
+    script::ScriptPlugin,
+    trap::TrapPlugin,
+    trigger::TriggerPlugin,
 };
 
 pub use asset::Level;
-pub use interactables::Interact;
+pub use change::NewGame;
+pub use error::LevelError;
+pub use fov::Viewshed;
+pub use interactables::{Interact, Interactable, Loot};
+pub use light::{CarriedLight, LightFuel};
+pub use mechanism::{Signal, SignalAction};
+pub use path::{PathStep, Pathfinder};
+pub use script::Script;
+pub use state::{LevelState, LevelStates};
+pub use trap::DisarmTrap;
 
 /// Holds a Handle to a Level Asset of the currently loaded level
 #[derive(Debug, Default, Resource, Deref)]
@@ -26,23 +54,47 @@ pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(InteractablePlugin)
-            .init_resource::<CurrentLevel>()
-            .init_asset::<Level>()
-            .init_asset_loader::<LevelAssetLoader>()
-            .add_event::<ChangeLevel>()
-            .add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(
-                Update,
-                (
-                    level_change_despawn,
-                    level_change_create,
-                    move_player_to_start_pos,
-                )
-                    .chain()
-                    .run_if(in_state(GameState::Playing))
-                    .after(interact), /* There is probably a better way for this */
-            );
+        app.add_plugins((
+            InteractablePlugin,
+            MechanismPlugin,
+            TriggerPlugin,
+            TrapPlugin,
+            ScriptPlugin,
+            LightPlugin,
+            FovPlugin,
+        ))
+        .init_resource::<CurrentLevel>()
+        .init_resource::<LevelStates>()
+        .init_asset::<Level>()
+        .init_asset_loader::<LevelAssetLoader>()
+        .add_event::<ChangeLevel>()
+        .add_event::<LevelReloaded>()
+        .add_event::<LevelError>()
+        /* Pausing and resuming keeps the level around */
+        .add_systems(
+            OnTransition {
+                exited: GameState::Menu,
+                entered: GameState::Playing,
+            },
+            setup,
+        )
+        .add_systems(OnEnter(GameState::Menu), teardown)
+        .add_systems(
+            Update,
+            (report_level_load_errors, report_level_errors).chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                reload_current_level,
+                level_change_despawn,
+                level_change_create,
+                move_player_to_start_pos,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing))
+                .after(interact), /* There is probably a better way for this */
+        );
     }
 }
 
@@ -53,8 +105,14 @@ pub enum Tile {
     #[default]
     Void,
     Stone,
+    /// Can only be crossed while a bridge spans it
+    Pit,
 }
 
 /* TODO: Move somewhere else */
 #[derive(Debug, Default, Component)]
 pub struct Player;
+
+/// Marks actors hostile to the player
+#[derive(Debug, Default, Component)]
+pub struct Enemy;
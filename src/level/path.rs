@@ -0,0 +1,234 @@
+use std::{cmp::Reverse, collections::BinaryHeap, collections::VecDeque};
+
+use bevy::utils::{HashMap, HashSet};
+
+use crate::movement::{GridDirection, GridPosition};
+
+use super::{asset::Level, state::LevelState};
+
+/// A single step of a path: the direction to face and the cell moving forward leads to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStep {
+    pub direction: GridDirection,
+    pub position: GridPosition,
+}
+
+/// Finds paths over the grid of a [`Level`].
+/// Void, pits, interactables (including closed doors) and cells occupied by other actors are impassable.
+#[derive(Debug)]
+pub struct Pathfinder<'a> {
+    level: &'a Level,
+    level_state: Option<&'a LevelState>,
+    occupied: HashSet<GridPosition>,
+}
+
+impl<'a> Pathfinder<'a> {
+    pub fn new(level: &'a Level) -> Self {
+        Self {
+            level,
+            level_state: None,
+            occupied: HashSet::default(),
+        }
+    }
+
+    /// Runtime state of the level, so opened doors and extended bridges can be passed
+    pub fn with_level_state(mut self, level_state: Option<&'a LevelState>) -> Self {
+        self.level_state = level_state;
+        self
+    }
+
+    /// Positions of other actors, which can not be passed
+    pub fn with_occupied(mut self, occupied: impl IntoIterator<Item = GridPosition>) -> Self {
+        self.occupied.extend(occupied);
+        self
+    }
+
+    pub fn is_passable(&self, position: &GridPosition) -> bool {
+        self.level.is_walkable(position, self.level_state) && !self.occupied.contains(position)
+    }
+
+    fn neighbours(
+        &self,
+        position: GridPosition,
+    ) -> impl Iterator<Item = (GridDirection, GridPosition)> + '_ {
+        GridDirection::ALL.into_iter().filter_map(move |direction| {
+            position
+                .next(&direction)
+                .ok()
+                .filter(|next| self.is_passable(next))
+                .map(|next| (direction, next))
+        })
+    }
+
+    /// Shortest path from `from` to `to` using A*.
+    /// The start is not part of the path, so an empty path means `from == to`.
+    pub fn find_path(&self, from: GridPosition, to: GridPosition) -> Option<Vec<PathStep>> {
+        if from != to && !self.is_passable(&to) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<GridPosition, PathStep> = HashMap::default();
+        let mut costs: HashMap<GridPosition, usize> = HashMap::default();
+
+        costs.insert(from, 0);
+        open.push(Reverse((from.manhattan_distance(&to), 0, from.x, from.y)));
+
+        while let Some(Reverse((_, cost, x, y))) = open.pop() {
+            let position = GridPosition { x, y };
+            if position == to {
+                return Some(Self::reconstruct(&came_from, from, to));
+            }
+            if costs.get(&position).is_some_and(|best| cost > *best) {
+                continue;
+            }
+
+            for (direction, next) in self.neighbours(position) {
+                let next_cost = cost + 1;
+                if costs.get(&next).is_none_or(|best| next_cost < *best) {
+                    costs.insert(next, next_cost);
+                    came_from.insert(
+                        next,
+                        PathStep {
+                            direction,
+                            position,
+                        },
+                    );
+                    open.push(Reverse((
+                        next_cost + next.manhattan_distance(&to),
+                        next_cost,
+                        next.x,
+                        next.y,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// `came_from` maps a cell to the cell it was reached from and the direction moved in
+    fn reconstruct(
+        came_from: &HashMap<GridPosition, PathStep>,
+        from: GridPosition,
+        to: GridPosition,
+    ) -> Vec<PathStep> {
+        let mut path = Vec::new();
+        let mut position = to;
+        while position != from {
+            let previous = came_from[&position];
+            path.push(PathStep {
+                direction: previous.direction,
+                position,
+            });
+            position = previous.position;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Number of steps from `from` to every reachable cell using BFS
+    pub fn distance_map(&self, from: GridPosition) -> HashMap<GridPosition, usize> {
+        let mut distances = HashMap::default();
+        let mut queue = VecDeque::new();
+
+        distances.insert(from, 0);
+        queue.push_back(from);
+
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[&position];
+            for (_, next) in self.neighbours(position) {
+                if !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* A ring of stone around void, the eastern side is closed by a door */
+    const LEVEL: &str = r#"(
+        grid: [
+            [Stone, Stone, Stone, Stone],
+            [Stone, Void, Void, Stone],
+            [Stone, Stone, Stone, Stone],
+        ],
+        start_pos: (x: 0, y: 0),
+        interactables: {
+            (x: 3, y: 1): Door(()),
+        },
+    )"#;
+
+    fn level() -> Level {
+        ron::de::from_str(LEVEL).unwrap()
+    }
+
+    fn position(x: usize, y: usize) -> GridPosition {
+        GridPosition { x, y }
+    }
+
+    #[test]
+    fn paths_go_around_void_and_interactables() {
+        let level = level();
+        let path = Pathfinder::new(&level)
+            .find_path(position(0, 0), position(3, 2))
+            .unwrap();
+
+        assert_eq!(
+            path.iter().map(|step| step.direction).collect::<Vec<_>>(),
+            [
+                GridDirection::South,
+                GridDirection::South,
+                GridDirection::East,
+                GridDirection::East,
+                GridDirection::East,
+            ]
+        );
+        assert_eq!(path.last().unwrap().position, position(3, 2));
+    }
+
+    #[test]
+    fn occupied_cells_block_the_way() {
+        let level = level();
+        let pathfinder = Pathfinder::new(&level).with_occupied([position(0, 1)]);
+
+        assert_eq!(pathfinder.find_path(position(0, 0), position(3, 2)), None);
+        assert!(pathfinder
+            .find_path(position(0, 0), position(3, 0))
+            .is_some());
+    }
+
+    #[test]
+    fn unreachable_targets_have_no_path() {
+        let level = level();
+        let pathfinder = Pathfinder::new(&level);
+
+        assert_eq!(pathfinder.find_path(position(0, 0), position(3, 1)), None);
+        assert_eq!(pathfinder.find_path(position(0, 0), position(1, 1)), None);
+        assert_eq!(pathfinder.find_path(position(0, 0), position(9, 9)), None);
+        assert_eq!(
+            pathfinder.find_path(position(0, 0), position(0, 0)),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn distance_maps_count_steps() {
+        let level = level();
+        let distances = Pathfinder::new(&level).distance_map(position(0, 0));
+
+        assert_eq!(distances.get(&position(0, 0)), Some(&0));
+        assert_eq!(distances.get(&position(3, 0)), Some(&3));
+        assert_eq!(distances.get(&position(0, 2)), Some(&2));
+        assert_eq!(distances.get(&position(3, 2)), Some(&5));
+        assert_eq!(distances.get(&position(3, 1)), None);
+        assert_eq!(distances.len(), 9);
+    }
+}
@@ -0,0 +1,692 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+use rhai::{
+    module_resolvers::DummyModuleResolver, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs,
+    Map, Position, Scope, AST,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    flags::{FlagValue, GameFlags},
+    item::{ItemId, Items, PickUp},
+    loading::{LevelAssets, ScriptAssets},
+    message_log::{LogMessage, MessageCategory},
+    movement::{GridPosition, Teleport, TileEntered, TurnTaken},
+    GameState,
+};
+
+use super::{
+    asset::Level,
+    change::ChangeLevel,
+    create::{move_player_to_start_pos, LevelGeometry},
+    error::LevelError,
+    fov::FogOfWar,
+    interactables::{Interact, Loot},
+    mechanism::{Signal, SignalAction},
+    state::LevelStates,
+    CurrentLevel, Player, TILE_SIZE,
+};
+
+pub struct ScriptPlugin;
+
+/// This plugin runs the [`Script`] of the current level, whenever something happens the script has a function for
+///
+/// Scripts can define `on_enter()`, `on_interact(x, y)` and `on_turn()`, only these functions are called,
+/// statements outside of them never run. State that should outlive a call is kept in the [`GameFlags`]
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Script>()
+            .init_asset_loader::<ScriptAssetLoader>()
+            .init_resource::<ScriptEngine>()
+            .add_systems(
+                Update,
+                (forget_changed_scripts, call_scripts, apply_script_commands)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .after(move_player_to_start_pos),
+            )
+            .add_systems(
+                Update,
+                pick_up_ground_items.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Source of a [Rhai](rhai) script, it is compiled when first called, so errors do not stop the loading
+#[derive(Debug, Asset, TypePath)]
+pub struct Script {
+    source: String,
+}
+
+/// What scripts ask the game to do, applied once the script returned
+#[derive(Debug)]
+enum ScriptCommand {
+    Message(String),
+    SetFlag(String, FlagValue),
+    ClearFlag(String),
+    Signal(String, SignalAction),
+    Teleport(GridPosition),
+    /// Teleports every actor standing on the first cell to the second
+    MoveActors(GridPosition, GridPosition),
+    GiveItem(ItemId, u32),
+    SpawnItem(Loot, GridPosition),
+    ChangeLevel(String, Option<GridPosition>),
+}
+
+/// The view of the game a running script has, scripts only ever see this copy
+#[derive(Debug, Default)]
+struct ScriptContext {
+    flags: GameFlags,
+    player: Option<GridPosition>,
+    commands: Vec<ScriptCommand>,
+}
+
+/// The sandboxed engine and the compiled scripts
+#[derive(Resource)]
+struct ScriptEngine {
+    engine: Engine,
+    context: Arc<Mutex<ScriptContext>>,
+    /// Scripts that failed to compile are kept as `None`, so the error is only reported once
+    compiled: HashMap<AssetId<Script>, Option<AST>>,
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        let context = Arc::new(Mutex::new(ScriptContext::default()));
+        let mut engine = Engine::new();
+
+        /* No imports, and limits, so a broken script cannot hang the game */
+        engine
+            .set_module_resolver(DummyModuleResolver::new())
+            .set_max_operations(100_000)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(4096)
+            .set_max_array_size(1024)
+            .set_max_map_size(256)
+            .disable_symbol("eval")
+            .on_print(|text| info!("Script: {text}"))
+            .on_debug(|text, source, position| {
+                debug!("Script {}:{position}: {text}", source.unwrap_or_default())
+            });
+
+        let push = |context: &Arc<Mutex<ScriptContext>>, command| {
+            context.lock().unwrap().commands.push(command);
+        };
+
+        let ctx = context.clone();
+        engine.register_fn("message", move |text: &str| {
+            push(&ctx, ScriptCommand::Message(text.to_string()));
+        });
+
+        let ctx = context.clone();
+        engine.register_fn("flag", move |flag: &str| -> Dynamic {
+            match ctx.lock().unwrap().flags.get(flag) {
+                Some(FlagValue::Bool(value)) => (*value).into(),
+                Some(FlagValue::Int(value)) => (*value).into(),
+                Some(FlagValue::Text(value)) => value.clone().into(),
+                None => Dynamic::UNIT,
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn("is_set", move |flag: &str| {
+            ctx.lock().unwrap().flags.is_set(flag)
+        });
+        let set_flag = |context: &Arc<Mutex<ScriptContext>>, flag: &str, value: FlagValue| {
+            let mut context = context.lock().unwrap();
+            /* Later reads in the same call see the new value */
+            context.flags.set(flag, value.clone());
+            context
+                .commands
+                .push(ScriptCommand::SetFlag(flag.to_string(), value));
+        };
+        let ctx = context.clone();
+        engine.register_fn("set_flag", move |flag: &str, value: bool| {
+            set_flag(&ctx, flag, FlagValue::Bool(value));
+        });
+        let ctx = context.clone();
+        engine.register_fn("set_flag", move |flag: &str, value: i64| {
+            set_flag(&ctx, flag, FlagValue::Int(value));
+        });
+        let ctx = context.clone();
+        engine.register_fn("set_flag", move |flag: &str, value: &str| {
+            set_flag(&ctx, flag, FlagValue::Text(value.to_string()));
+        });
+        let ctx = context.clone();
+        engine.register_fn("clear_flag", move |flag: &str| {
+            let mut context = ctx.lock().unwrap();
+            context.flags.clear(flag);
+            context
+                .commands
+                .push(ScriptCommand::ClearFlag(flag.to_string()));
+        });
+
+        for (name, action) in [
+            ("toggle", SignalAction::Toggle),
+            ("activate", SignalAction::Activate),
+            ("deactivate", SignalAction::Deactivate),
+        ] {
+            let ctx = context.clone();
+            engine.register_fn(name, move |target: &str| {
+                push(&ctx, ScriptCommand::Signal(target.to_string(), action));
+            });
+        }
+
+        let ctx = context.clone();
+        engine.register_fn("player_position", move || -> Dynamic {
+            match ctx.lock().unwrap().player {
+                Some(position) => {
+                    let mut map = Map::new();
+                    map.insert("x".into(), (position.x as i64).into());
+                    map.insert("y".into(), (position.y as i64).into());
+                    map.into()
+                }
+                None => Dynamic::UNIT,
+            }
+        });
+
+        let ctx = context.clone();
+        engine.register_fn(
+            "teleport",
+            move |x: i64, y: i64| -> Result<(), Box<EvalAltResult>> {
+                let position = grid_position(x, y)?;
+                push(&ctx, ScriptCommand::Teleport(position));
+                Ok(())
+            },
+        );
+
+        let ctx = context.clone();
+        engine.register_fn(
+            "move_actors",
+            move |x: i64, y: i64, to_x: i64, to_y: i64| -> Result<(), Box<EvalAltResult>> {
+                let from = grid_position(x, y)?;
+                let to = grid_position(to_x, to_y)?;
+                push(&ctx, ScriptCommand::MoveActors(from, to));
+                Ok(())
+            },
+        );
+
+        let ctx = context.clone();
+        engine.register_fn("give_item", move |item: &str| {
+            push(&ctx, ScriptCommand::GiveItem(item.into(), 1));
+        });
+        let ctx = context.clone();
+        engine.register_fn(
+            "give_item",
+            move |item: &str, count: i64| -> Result<(), Box<EvalAltResult>> {
+                let count =
+                    u32::try_from(count).map_err(|_| format!("Cannot give {count} of an item"))?;
+                push(&ctx, ScriptCommand::GiveItem(item.into(), count));
+                Ok(())
+            },
+        );
+
+        /* Each item is spawned on a cell only once, so `on_enter()` can spawn it every time */
+        let ctx = context.clone();
+        engine.register_fn(
+            "spawn_item",
+            move |item: &str, x: i64, y: i64| -> Result<(), Box<EvalAltResult>> {
+                let loot = loot(item, 1)?;
+                push(&ctx, ScriptCommand::SpawnItem(loot, grid_position(x, y)?));
+                Ok(())
+            },
+        );
+        let ctx = context.clone();
+        engine.register_fn(
+            "spawn_item",
+            move |item: &str, x: i64, y: i64, count: i64| -> Result<(), Box<EvalAltResult>> {
+                let loot = loot(item, count)?;
+                push(&ctx, ScriptCommand::SpawnItem(loot, grid_position(x, y)?));
+                Ok(())
+            },
+        );
+
+        let ctx = context.clone();
+        engine.register_fn("change_level", move |level: &str| {
+            push(&ctx, ScriptCommand::ChangeLevel(level.to_string(), None));
+        });
+        let ctx = context.clone();
+        engine.register_fn(
+            "change_level",
+            move |level: &str, x: i64, y: i64| -> Result<(), Box<EvalAltResult>> {
+                let position = grid_position(x, y)?;
+                push(
+                    &ctx,
+                    ScriptCommand::ChangeLevel(level.to_string(), Some(position)),
+                );
+                Ok(())
+            },
+        );
+
+        Self {
+            engine,
+            context,
+            compiled: HashMap::default(),
+        }
+    }
+}
+
+fn loot(item: &str, count: i64) -> Result<Loot, Box<EvalAltResult>> {
+    match u32::try_from(count) {
+        Ok(count) if count > 0 => Ok(Loot {
+            item: item.into(),
+            count,
+        }),
+        _ => Err(format!("Cannot spawn {count} of an item").into()),
+    }
+}
+
+fn grid_position(x: i64, y: i64) -> Result<GridPosition, Box<EvalAltResult>> {
+    match (usize::try_from(x), usize::try_from(y)) {
+        (Ok(x), Ok(y)) => Ok(GridPosition { x, y }),
+        _ => Err(format!("({x}, {y}) is outside of the level").into()),
+    }
+}
+
+impl ScriptEngine {
+    /// Calls the function, if the script has it, errors are reported instead of returned
+    fn call(
+        &mut self,
+        handle: &Handle<Script>,
+        scripts: &Assets<Script>,
+        function: &str,
+        args: impl FuncArgs,
+        level_error_evw: &mut EventWriter<LevelError>,
+    ) {
+        let path = handle.path().map(ToString::to_string).unwrap_or_default();
+        let Some(script) = scripts.get(handle) else {
+            return;
+        };
+
+        let ast = self.compiled.entry(handle.id()).or_insert_with(|| {
+            match self.engine.compile(&script.source) {
+                Ok(mut ast) => {
+                    ast.set_source(path.as_str());
+                    Some(ast)
+                }
+                Err(error) => {
+                    level_error_evw.send(LevelError::ScriptFailed {
+                        location: location(&path, error.position()),
+                        reason: error.err_type().to_string(),
+                    });
+                    None
+                }
+            }
+        });
+        let Some(ast) = ast else {
+            return;
+        };
+        if !ast.iter_functions().any(|f| f.name == function) {
+            return;
+        }
+
+        /* Only the function runs, statements outside of functions are never evaluated */
+        let options = CallFnOptions::new().eval_ast(false);
+        let mut scope = Scope::new();
+        if let Err(error) = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut scope, ast, function, args)
+        {
+            /* Report where the error happened, not where the failing function was called */
+            let mut error = *error;
+            while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = error {
+                error = *inner;
+            }
+            let position = error.take_position();
+            level_error_evw.send(LevelError::ScriptFailed {
+                location: location(&path, position),
+                reason: error.to_string(),
+            });
+        }
+    }
+}
+
+fn location(path: &str, position: Position) -> String {
+    match position.line() {
+        Some(line) => format!("{path}:{line}"),
+        None => path.to_string(),
+    }
+}
+
+/// Hot reloaded scripts are compiled again
+fn forget_changed_scripts(
+    mut script_evr: EventReader<AssetEvent<Script>>,
+    mut script_engine: ResMut<ScriptEngine>,
+) {
+    for event in script_evr.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            script_engine.compiled.remove(id);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_scripts(
+    mut change_level_evr: EventReader<ChangeLevel>,
+    mut interact_evr: EventReader<Interact>,
+    mut turn_taken_evr: EventReader<TurnTaken>,
+    mut script_engine: ResMut<ScriptEngine>,
+    mut level_error_evw: EventWriter<LevelError>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    scripts: Res<Assets<Script>>,
+    script_assets: Res<ScriptAssets>,
+    flags: Res<GameFlags>,
+    players: Query<&GridPosition, With<Player>>,
+) {
+    let entered = change_level_evr
+        .read()
+        .filter_map(|event| levels.get(&event.level))
+        .collect::<Vec<_>>();
+    let interactions = interact_evr
+        .read()
+        .filter(|event| players.contains(event.source))
+        .map(|event| event.position)
+        .collect::<Vec<_>>();
+    let turns = turn_taken_evr
+        .read()
+        .filter(|event| players.contains(event.entity))
+        .count();
+    if entered.is_empty() && interactions.is_empty() && turns == 0 {
+        return;
+    }
+
+    {
+        let mut context = script_engine.context.lock().unwrap();
+        context.flags = flags.clone();
+        context.player = players.iter().next().copied();
+    }
+
+    let script_of = |level: &Level, level_error_evw: &mut EventWriter<LevelError>| {
+        let path = level.script.as_ref()?;
+        let handle = script_assets.scripts.get(path);
+        if handle.is_none() {
+            level_error_evw.send(LevelError::UnknownScript(path.clone()));
+        }
+        handle.cloned()
+    };
+
+    for level in entered {
+        if let Some(handle) = script_of(level, &mut level_error_evw) {
+            script_engine.call(&handle, &scripts, "on_enter", (), &mut level_error_evw);
+        }
+    }
+
+    let Some(handle) = levels
+        .get(&current_level.0)
+        .and_then(|level| level.script.as_ref())
+        .and_then(|path| script_assets.scripts.get(path))
+    else {
+        return;
+    };
+    for position in interactions {
+        let args = (position.x as i64, position.y as i64);
+        script_engine.call(handle, &scripts, "on_interact", args, &mut level_error_evw);
+    }
+    for _ in 0..turns {
+        script_engine.call(handle, &scripts, "on_turn", (), &mut level_error_evw);
+    }
+}
+
+/// Items spawned by scripts, they are picked up by stepping onto their cell
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+pub struct GroundItem {
+    loot: Loot,
+    position: GridPosition,
+}
+
+impl GroundItem {
+    /// Items without a model lie there unseen
+    pub fn spawn(&self, commands: &mut Commands, items: &Items) {
+        let transform = Transform::from_translation(
+            Vec3::from(&self.position) + Vec3::new(0.0, -TILE_SIZE / 2.0, 0.0),
+        );
+        let mut entity = match items
+            .get(&self.loot.item)
+            .and_then(|item| item.model.clone())
+        {
+            Some(model) => commands.spawn(SceneBundle {
+                scene: model,
+                transform: transform.with_scale(Vec3::splat(8.0)),
+                ..default()
+            }),
+            None => commands.spawn(SpatialBundle::from_transform(transform)),
+        };
+        entity.insert((self.clone(), LevelGeometry, FogOfWar(self.position)));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_script_commands(
+    mut commands: Commands,
+    script_engine: Res<ScriptEngine>,
+    level_assets: Res<LevelAssets>,
+    items: Items,
+    mut flags: ResMut<GameFlags>,
+    players: Query<Entity, With<Player>>,
+    actors: Query<(Entity, &GridPosition)>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut signal_evw: EventWriter<Signal>,
+    mut teleport_evw: EventWriter<Teleport>,
+    mut pick_up_evw: EventWriter<PickUp>,
+    mut change_level_evw: EventWriter<ChangeLevel>,
+    mut level_error_evw: EventWriter<LevelError>,
+    current_level: Res<CurrentLevel>,
+    mut level_states: ResMut<LevelStates>,
+) {
+    let script_commands = std::mem::take(&mut script_engine.context.lock().unwrap().commands);
+    for command in script_commands {
+        match command {
+            ScriptCommand::Message(text) => {
+                log_message_evw.send(LogMessage::new(MessageCategory::Level, text));
+            }
+            ScriptCommand::SetFlag(flag, value) => flags.set(flag, value),
+            ScriptCommand::ClearFlag(flag) => flags.clear(&flag),
+            ScriptCommand::Signal(target, action) => {
+                signal_evw.send(Signal { target, action });
+            }
+            ScriptCommand::Teleport(position) => {
+                for entity in &players {
+                    teleport_evw.send(Teleport { entity, position });
+                }
+            }
+            ScriptCommand::MoveActors(from, to) => {
+                teleport_evw.send_batch(
+                    actors
+                        .iter()
+                        .filter(|(_, position)| **position == from)
+                        .map(|(entity, _)| Teleport {
+                            entity,
+                            position: to,
+                        }),
+                );
+            }
+            ScriptCommand::GiveItem(item, count) => {
+                pick_up_evw.send_batch(players.iter().map(|entity| PickUp {
+                    entity,
+                    item: item.clone(),
+                    count,
+                }));
+            }
+            ScriptCommand::SpawnItem(loot, position) => {
+                let level_state = level_states.get_mut(&current_level.0);
+                if !level_state
+                    .spawned_items
+                    .insert((position, loot.item.clone()))
+                {
+                    continue;
+                }
+                let ground_item = GroundItem { loot, position };
+                ground_item.spawn(&mut commands, &items);
+                level_state.ground_items.push(ground_item);
+            }
+            ScriptCommand::ChangeLevel(level_name, position) => {
+                match level_assets.levels.get(&level_name) {
+                    Some(level) => {
+                        change_level_evw.send(ChangeLevel {
+                            level: level.clone(),
+                            position,
+                            direction: None,
+                        });
+                    }
+                    None => {
+                        level_error_evw.send(LevelError::UnknownLevel(level_name));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn pick_up_ground_items(
+    mut commands: Commands,
+    mut tile_entered_evr: EventReader<TileEntered>,
+    players: Query<(), With<Player>>,
+    ground_items: Query<(Entity, &GroundItem)>,
+    mut pick_up_evw: EventWriter<PickUp>,
+    current_level: Res<CurrentLevel>,
+    mut level_states: ResMut<LevelStates>,
+) {
+    for event in tile_entered_evr.read() {
+        if !players.contains(event.entity) {
+            continue;
+        }
+        level_states
+            .get_mut(&current_level.0)
+            .ground_items
+            .retain(|ground_item| ground_item.position != event.position);
+        for (entity, ground_item) in &ground_items {
+            if ground_item.position == event.position {
+                pick_up_evw.send(PickUp {
+                    entity: event.entity,
+                    item: ground_item.loot.item.clone(),
+                    count: ground_item.loot.count,
+                });
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ScriptAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// Scripts must be UTF-8
+    #[error("Could not read script: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+}
+
+#[derive(Default)]
+pub struct ScriptAssetLoader;
+
+impl AssetLoader for ScriptAssetLoader {
+    type Asset = Script;
+    type Settings = ();
+    type Error = ScriptAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(Script {
+            source: String::from_utf8(bytes)?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{item::ItemDatabase, loading::ItemAssets};
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<LogMessage>()
+            .add_event::<Signal>()
+            .add_event::<Teleport>()
+            .add_event::<PickUp>()
+            .add_event::<ChangeLevel>()
+            .add_event::<LevelError>()
+            .add_event::<TileEntered>()
+            .init_resource::<ScriptEngine>()
+            .init_resource::<GameFlags>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<LevelStates>()
+            .init_resource::<Assets<ItemDatabase>>()
+            .insert_resource(ItemAssets {
+                database: Handle::default(),
+            })
+            .insert_resource(LevelAssets { levels: default() })
+            .add_systems(
+                Update,
+                (apply_script_commands, pick_up_ground_items).chain(),
+            );
+        app
+    }
+
+    /// What `on_enter()` does each time the level is entered
+    fn spawn_coins(app: &mut App) {
+        let script_engine = app.world().resource::<ScriptEngine>();
+        script_engine
+            .context
+            .lock()
+            .unwrap()
+            .commands
+            .push(ScriptCommand::SpawnItem(
+                Loot {
+                    item: ItemId("coin".to_string()),
+                    count: 3,
+                },
+                GridPosition { x: 2, y: 1 },
+            ));
+        app.update();
+    }
+
+    fn ground_items(app: &mut App) -> (usize, usize) {
+        let world = app.world_mut();
+        let spawned = world.query::<&GroundItem>().iter(world).count();
+        let recorded = world
+            .resource::<LevelStates>()
+            .get(&CurrentLevel::default().0)
+            .map_or(0, |level_state| level_state.ground_items.len());
+        (spawned, recorded)
+    }
+
+    #[test]
+    fn ground_items_are_spawned_once() {
+        let mut app = app();
+        spawn_coins(&mut app);
+        spawn_coins(&mut app);
+        assert_eq!(ground_items(&mut app), (1, 1));
+
+        let player = app.world_mut().spawn(Player).id();
+        app.world_mut().send_event(TileEntered {
+            entity: player,
+            position: GridPosition { x: 2, y: 1 },
+        });
+        app.update();
+        assert_eq!(ground_items(&mut app), (0, 0));
+
+        /* Entering the level again does not bring it back */
+        spawn_coins(&mut app);
+        assert_eq!(ground_items(&mut app), (0, 0));
+    }
+}
@@ -0,0 +1,70 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{item::ItemId, movement::GridPosition};
+
+use super::{asset::Level, script::GroundItem};
+
+/// Runtime state of every visited level, which outlives level changes
+#[derive(Debug, Default, Resource)]
+pub struct LevelStates(HashMap<AssetId<Level>, LevelState>);
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelState {
+    /// Cells the player has seen with their light
+    pub explored: HashSet<GridPosition>,
+    /// Chests that were looted already
+    pub opened_chests: HashSet<GridPosition>,
+    /// Mechanisms that were switched, the others are as the level file sets them up
+    pub mechanisms: HashMap<String, bool>,
+    /// Pressure plates something is standing on or that latched
+    pub pressed_plates: HashSet<GridPosition>,
+    /// Cells whose one-time triggers have fired
+    pub fired_triggers: HashSet<GridPosition>,
+    /// Traps the player knows of, visible traps are detected once seen
+    pub detected_traps: HashSet<GridPosition>,
+    pub disarmed_traps: HashSet<GridPosition>,
+    /// Traps that went off at least once
+    pub sprung_traps: HashSet<GridPosition>,
+    /// Items scripts spawned, which were not picked up yet
+    pub ground_items: Vec<GroundItem>,
+    /// Scripts spawn an item on a cell only once, so entering the level again does not spawn it twice
+    pub spawned_items: HashSet<(GridPosition, ItemId)>,
+    /// Enemies with loot defeated so far, it seeds the drop of the next one
+    pub defeated_enemies: u64,
+}
+
+impl LevelStates {
+    pub fn get(&self, level: &Handle<Level>) -> Option<&LevelState> {
+        self.0.get(&level.id())
+    }
+
+    pub fn get_mut(&mut self, level: &Handle<Level>) -> &mut LevelState {
+        self.0.entry(level.id()).or_default()
+    }
+
+    /// The states by the path of their level, e.g. `level/000.lvl`, which stays the same between runs
+    pub fn by_path(&self, levels: &HashMap<String, Handle<Level>>) -> HashMap<String, LevelState> {
+        levels
+            .iter()
+            .filter_map(|(path, level)| Some((path.clone(), self.get(level)?.clone())))
+            .collect()
+    }
+
+    /// The inverse of [`LevelStates::by_path`], states of unknown levels are dropped
+    pub fn from_paths(
+        states: &HashMap<String, LevelState>,
+        levels: &HashMap<String, Handle<Level>>,
+    ) -> Self {
+        Self(
+            states
+                .iter()
+                .filter_map(|(path, state)| Some((levels.get(path)?.id(), state.clone())))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,469 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    health::{Damage, Health},
+    loading::LevelAssets,
+    message_log::{LogMessage, MessageCategory},
+    movement::{GridDirection, GridPosition, TileEntered},
+    GameState,
+};
+
+use super::{
+    asset::Level, change::ChangeLevel, error::LevelError, state::LevelStates, CurrentLevel, Player,
+    Viewshed,
+};
+
+pub struct TrapPlugin;
+
+/// This plugin springs, detects and disarms the [`Trap`]s of the current level.
+/// Traps use no randomness, so replays spring them the same way.
+impl Plugin for TrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DisarmTrap>().add_systems(
+            Update,
+            (detect_traps, disarm_traps, spring_traps, show_traps)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// The entity tries to disarm a trap on the cell in front of it
+#[derive(Debug, Event)]
+pub struct DisarmTrap {
+    pub entity: Entity,
+    pub position: GridPosition,
+}
+
+/// Springs when an entity enters the cell
+#[derive(Debug, Clone, Deserialize)]
+pub struct Trap {
+    pub grid_position: GridPosition,
+    pub kind: TrapKind,
+    /// Hidden traps have to be noticed from up close, before they are shown and can be disarmed
+    #[serde(default)]
+    pub hidden: bool,
+    /// How many cells away the player notices a hidden trap they can see
+    #[serde(default = "default_detect_range")]
+    pub detect_range: usize,
+    /// Springs every time the cell is entered, instead of just once
+    #[serde(default)]
+    pub rearm: bool,
+}
+
+fn default_detect_range() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum TrapKind {
+    /// Hurts the entity on the cell
+    Spikes { damage: u32 },
+    /// Shoots a dart from `origin` along `direction`, which hits the first actor in its way
+    Darts {
+        origin: GridPosition,
+        direction: GridDirection,
+        damage: u32,
+        #[serde(default = "default_dart_range")]
+        range: usize,
+    },
+    /// The floor gives way and the player falls into `level`, the cell stays a pit
+    Collapse {
+        level: String,
+        #[serde(default)]
+        position: Option<GridPosition>,
+        damage: u32,
+    },
+}
+
+fn default_dart_range() -> usize {
+    8
+}
+
+impl Trap {
+    /// Collapsed floors can be walked around, but not disarmed
+    fn is_disarmable(&self) -> bool {
+        !matches!(self.kind, TrapKind::Collapse { .. })
+    }
+}
+
+/// Shows a detected trap that is still armed
+#[derive(Debug, Component)]
+pub struct TrapMarker(pub GridPosition);
+
+fn detect_traps(
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut level_states: ResMut<LevelStates>,
+    players: Query<(&GridPosition, &Viewshed), (With<Player>, Changed<Viewshed>)>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for (grid_position, viewshed) in &players {
+        let level_state = level_states.get_mut(&current_level.0);
+        for trap in &level.traps {
+            let position = trap.grid_position;
+            if level_state.detected_traps.contains(&position)
+                || !viewshed.visible.contains(&position)
+            {
+                continue;
+            }
+            if trap.hidden {
+                if grid_position.manhattan_distance(&position) > trap.detect_range {
+                    continue;
+                }
+                log_message_evw.send(LogMessage::new(
+                    MessageCategory::Interaction,
+                    "You notice a hidden trap",
+                ));
+            }
+            level_state.detected_traps.insert(position);
+        }
+    }
+}
+
+fn disarm_traps(
+    mut disarm_trap_evr: EventReader<DisarmTrap>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut level_states: ResMut<LevelStates>,
+    players: Query<(), With<Player>>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for event in disarm_trap_evr.read() {
+        if !players.contains(event.entity) {
+            continue;
+        }
+        let level_state = level_states.get_mut(&current_level.0);
+        let Some(trap) = level.trap(&event.position) else {
+            continue;
+        };
+        /* Detection is what the player knows, they can not disarm what they did not notice */
+        if !level_state.detected_traps.contains(&event.position)
+            || level_state.disarmed_traps.contains(&event.position)
+        {
+            continue;
+        }
+
+        let text = if trap.is_disarmable() {
+            level_state.disarmed_traps.insert(event.position);
+            "You disarm the trap"
+        } else {
+            "There is nothing to disarm, the floor is just brittle"
+        };
+        log_message_evw.send(LogMessage::new(MessageCategory::Interaction, text));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spring_traps(
+    mut tile_entered_evr: EventReader<TileEntered>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    levels: Res<LevelAssets>,
+    mut level_states: ResMut<LevelStates>,
+    actors: Query<(Entity, &GridPosition, Has<Player>), With<Health>>,
+    mut damage_evw: EventWriter<Damage>,
+    mut change_level_evw: EventWriter<ChangeLevel>,
+    mut level_error_evw: EventWriter<LevelError>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for event in tile_entered_evr.read() {
+        let Some(trap) = level.trap(&event.position) else {
+            continue;
+        };
+        let level_state = level_states.get_mut(&current_level.0);
+        if level_state.disarmed_traps.contains(&event.position)
+            || (level_state.sprung_traps.contains(&event.position) && !trap.rearm)
+        {
+            continue;
+        }
+
+        level_state.sprung_traps.insert(event.position);
+        level_state.detected_traps.insert(event.position);
+        let is_player = actors
+            .get(event.entity)
+            .is_ok_and(|(_, _, is_player)| is_player);
+
+        match &trap.kind {
+            TrapKind::Spikes { damage } => {
+                if is_player {
+                    log_message_evw.send(LogMessage::new(
+                        MessageCategory::Combat,
+                        "Spikes shoot out of the floor",
+                    ));
+                }
+                damage_evw.send(Damage {
+                    entity: event.entity,
+                    amount: *damage,
+                });
+            }
+            TrapKind::Darts {
+                origin,
+                direction,
+                damage,
+                range,
+            } => {
+                log_message_evw.send(LogMessage::new(
+                    MessageCategory::Combat,
+                    "A dart shoots out of the wall",
+                ));
+                /* The dart flies until it hits an actor or something solid */
+                let mut position = *origin;
+                for _ in 0..*range {
+                    let Ok(next) = position.next(direction) else {
+                        break;
+                    };
+                    if level.is_opaque(&next, Some(level_state)) {
+                        break;
+                    }
+                    position = next;
+
+                    if let Some((target, _, _)) = actors
+                        .iter()
+                        .find(|(_, grid_position, _)| **grid_position == position)
+                    {
+                        damage_evw.send(Damage {
+                            entity: target,
+                            amount: *damage,
+                        });
+                        break;
+                    }
+                }
+            }
+            TrapKind::Collapse {
+                level: level_name,
+                position,
+                damage,
+            } => {
+                damage_evw.send(Damage {
+                    entity: event.entity,
+                    amount: *damage,
+                });
+                if !is_player {
+                    continue;
+                }
+
+                log_message_evw.send(LogMessage::new(
+                    MessageCategory::Combat,
+                    "The floor gives way beneath you",
+                ));
+                match levels.levels.get(level_name) {
+                    Some(level) => {
+                        change_level_evw.send(ChangeLevel {
+                            level: level.clone(),
+                            position: *position,
+                            direction: None,
+                        });
+                    }
+                    None => {
+                        level_error_evw.send(LevelError::UnknownLevel(level_name.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn show_traps(
+    current_level: Res<CurrentLevel>,
+    level_states: Res<LevelStates>,
+    mut markers: Query<(&TrapMarker, &mut Visibility)>,
+) {
+    if !level_states.is_changed() {
+        return;
+    }
+    let Some(level_state) = level_states.get(&current_level.0) else {
+        return;
+    };
+
+    for (marker, mut visibility) in &mut markers {
+        let shown = level_state.detected_traps.contains(&marker.0)
+            && !level_state.disarmed_traps.contains(&marker.0);
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LevelState;
+
+    const LEVEL: &str = r#"(
+        grid: [
+            [Stone, Stone, Stone, Stone, Stone],
+            [Stone, Stone, Stone, Stone, Stone],
+            [Stone, Stone, Stone, Stone, Stone],
+        ],
+        start_pos: (x: 0, y: 1),
+        traps: [
+            (grid_position: (x: 1, y: 1), kind: Spikes(damage: 2)),
+            (grid_position: (x: 3, y: 1), kind: Spikes(damage: 1), hidden: true),
+            (
+                grid_position: (x: 2, y: 2),
+                kind: Darts(origin: (x: 0, y: 2), direction: East, damage: 3),
+                rearm: true,
+            ),
+        ],
+    )"#;
+
+    fn position(x: usize, y: usize) -> GridPosition {
+        GridPosition { x, y }
+    }
+
+    /// The trap systems on the test level, without rendering or game states
+    fn app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<TileEntered>()
+            .add_event::<DisarmTrap>()
+            .add_event::<Damage>()
+            .add_event::<ChangeLevel>()
+            .add_event::<LevelError>()
+            .add_event::<LogMessage>()
+            .init_resource::<LevelStates>()
+            .init_resource::<Assets<Level>>()
+            .insert_resource(LevelAssets { levels: default() })
+            .add_systems(Update, (detect_traps, disarm_traps, spring_traps).chain());
+
+        let level = ron::de::from_str::<Level>(LEVEL).unwrap();
+        let handle = app.world_mut().resource_mut::<Assets<Level>>().add(level);
+        app.insert_resource(CurrentLevel(handle));
+
+        let player = app
+            .world_mut()
+            .spawn((Player, position(0, 1), Viewshed::new(8), Health::new(10)))
+            .id();
+        (app, player)
+    }
+
+    /// Moves the entity onto the cell and returns the damage dealt on the way
+    fn enter(app: &mut App, entity: Entity, position: GridPosition) -> Vec<(Entity, u32)> {
+        app.world_mut().entity_mut(entity).insert(position);
+        app.world_mut().send_event(TileEntered { entity, position });
+        app.update();
+        app.world_mut()
+            .resource_mut::<Events<Damage>>()
+            .drain()
+            .map(|damage| (damage.entity, damage.amount))
+            .collect()
+    }
+
+    fn see(app: &mut App, entity: Entity, cells: &[GridPosition]) {
+        let mut entity = app.world_mut().entity_mut(entity);
+        let mut viewshed = entity.get_mut::<Viewshed>().unwrap();
+        viewshed.visible = cells.iter().copied().collect();
+        app.update();
+    }
+
+    fn disarm(app: &mut App, entity: Entity, position: GridPosition) {
+        app.world_mut().send_event(DisarmTrap { entity, position });
+        app.update();
+    }
+
+    fn level_state(app: &App) -> &LevelState {
+        let current_level = app.world().resource::<CurrentLevel>();
+        app.world()
+            .resource::<LevelStates>()
+            .get(current_level)
+            .unwrap()
+    }
+
+    #[test]
+    fn spikes_spring_once() {
+        let (mut app, player) = app();
+
+        assert_eq!(enter(&mut app, player, position(1, 1)), [(player, 2)]);
+        assert!(level_state(&app).sprung_traps.contains(&position(1, 1)));
+        assert!(level_state(&app).detected_traps.contains(&position(1, 1)));
+
+        assert_eq!(enter(&mut app, player, position(0, 1)), []);
+        assert_eq!(enter(&mut app, player, position(1, 1)), []);
+    }
+
+    #[test]
+    fn hidden_traps_are_noticed_from_up_close() {
+        let (mut app, player) = app();
+        let cells = [position(1, 1), position(2, 1), position(3, 1)];
+
+        see(&mut app, player, &cells);
+        let detected = &level_state(&app).detected_traps;
+        assert!(detected.contains(&position(1, 1)));
+        assert!(!detected.contains(&position(3, 1)));
+
+        app.world_mut().entity_mut(player).insert(position(2, 1));
+        see(&mut app, player, &cells);
+        assert!(level_state(&app).detected_traps.contains(&position(3, 1)));
+    }
+
+    #[test]
+    fn only_noticed_traps_are_disarmed() {
+        let (mut app, player) = app();
+
+        disarm(&mut app, player, position(3, 1));
+        assert!(level_state(&app).disarmed_traps.is_empty());
+
+        app.world_mut().entity_mut(player).insert(position(2, 1));
+        see(&mut app, player, &[position(3, 1)]);
+        disarm(&mut app, player, position(3, 1));
+        assert!(level_state(&app).disarmed_traps.contains(&position(3, 1)));
+
+        assert_eq!(enter(&mut app, player, position(3, 1)), []);
+        assert!(!level_state(&app).sprung_traps.contains(&position(3, 1)));
+    }
+
+    #[test]
+    fn darts_hit_the_first_actor_in_their_way() {
+        let (mut app, player) = app();
+        let bystander = app
+            .world_mut()
+            .spawn((position(1, 2), Health::new(10)))
+            .id();
+
+        assert_eq!(enter(&mut app, player, position(2, 2)), [(bystander, 3)]);
+
+        /* The trap rearms, and with the way clear the dart reaches the player */
+        app.world_mut().despawn(bystander);
+        assert_eq!(enter(&mut app, player, position(2, 1)), []);
+        assert_eq!(enter(&mut app, player, position(2, 2)), [(player, 3)]);
+    }
+
+    #[test]
+    fn replays_spring_traps_the_same_way() {
+        let replay = || {
+            let (mut app, player) = app();
+            let mut damage = Vec::new();
+            see(&mut app, player, &[position(1, 1), position(3, 1)]);
+            for x in 1..5 {
+                damage.extend(enter(&mut app, player, position(x, 1)));
+                damage.extend(enter(&mut app, player, position(x, 2)));
+            }
+            disarm(&mut app, player, position(1, 1));
+
+            let level_state = level_state(&app);
+            let cells = [
+                level_state.detected_traps.clone(),
+                level_state.disarmed_traps.clone(),
+                level_state.sprung_traps.clone(),
+            ];
+            (damage, cells)
+        };
+
+        assert_eq!(replay(), replay());
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    condition::Condition,
+    flags::{FlagValue, GameFlags},
+    health::Damage,
+    inventory::Inventory,
+    loading::LevelAssets,
+    message_log::{LogMessage, MessageCategory},
+    movement::{GridPosition, Teleport, TileEntered},
+    GameState,
+};
+
+use super::{
+    asset::Level,
+    change::ChangeLevel,
+    error::LevelError,
+    mechanism::{Signal, SignalAction},
+    state::LevelStates,
+    CurrentLevel, Player,
+};
+
+pub struct TriggerPlugin;
+
+/// This plugin fires the [`TileTrigger`]s of cells, whenever an entity enters them
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, fire_triggers.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Actions that are run when an entity steps on a cell
+#[derive(Debug, Clone, Deserialize)]
+pub struct TileTrigger {
+    pub actions: Vec<TriggerAction>,
+    /// Only fires the first time the cell is entered
+    #[serde(default)]
+    pub once: bool,
+    /// Fires for every entity, not just the player
+    #[serde(default)]
+    pub any_actor: bool,
+    /// Only fires while the condition is met, checked for the entity that entered the cell
+    #[serde(default)]
+    pub requires: Option<Condition>,
+}
+
+impl TileTrigger {
+    /// The ids of the mechanisms the trigger switches
+    pub fn targets(&self) -> impl Iterator<Item = &String> {
+        self.actions.iter().filter_map(|action| match action {
+            TriggerAction::Signal(target, _) => Some(target),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum TriggerAction {
+    /// Adds a line to the message log
+    Message(String),
+    /// Switches the mechanism with the id
+    Signal(String, SignalAction),
+    SetFlag(String, FlagValue),
+    ClearFlag(String),
+    /// Counts an integer flag up, or down with a negative amount
+    AddToFlag(String, i64),
+    /// Hurts the entity that entered the cell
+    Damage(u32),
+    /// Moves the entity that entered the cell elsewhere in the level
+    Teleport(GridPosition),
+    /// Moves the player to another level, at its start position unless `position` is set
+    ChangeLevel {
+        level: String,
+        #[serde(default)]
+        position: Option<GridPosition>,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_triggers(
+    mut tile_entered_evr: EventReader<TileEntered>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    levels: Res<LevelAssets>,
+    mut level_states: ResMut<LevelStates>,
+    mut flags: ResMut<GameFlags>,
+    players: Query<(), With<Player>>,
+    inventories: Query<&Inventory>,
+    mut log_message_evw: EventWriter<LogMessage>,
+    mut signal_evw: EventWriter<Signal>,
+    mut damage_evw: EventWriter<Damage>,
+    mut teleport_evw: EventWriter<Teleport>,
+    mut change_level_evw: EventWriter<ChangeLevel>,
+    mut level_error_evw: EventWriter<LevelError>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for event in tile_entered_evr.read() {
+        let Some(trigger) = level.triggers.get(&event.position) else {
+            continue;
+        };
+        if !trigger.any_actor && !players.contains(event.entity) {
+            continue;
+        }
+        if !trigger
+            .requires
+            .as_ref()
+            .is_none_or(|condition| condition.is_met(&flags, inventories.get(event.entity).ok()))
+        {
+            continue;
+        }
+        if trigger.once
+            && !level_states
+                .get_mut(&current_level.0)
+                .fired_triggers
+                .insert(event.position)
+        {
+            continue;
+        }
+
+        for action in &trigger.actions {
+            match action {
+                TriggerAction::Message(text) => {
+                    log_message_evw.send(LogMessage::new(MessageCategory::Level, text.clone()));
+                }
+                TriggerAction::Signal(target, action) => {
+                    signal_evw.send(Signal {
+                        target: target.clone(),
+                        action: *action,
+                    });
+                }
+                TriggerAction::SetFlag(flag, value) => flags.set(flag, value.clone()),
+                TriggerAction::ClearFlag(flag) => flags.clear(flag),
+                TriggerAction::AddToFlag(flag, amount) => flags.add(flag, *amount),
+                TriggerAction::Damage(amount) => {
+                    damage_evw.send(Damage {
+                        entity: event.entity,
+                        amount: *amount,
+                    });
+                }
+                TriggerAction::Teleport(position) => {
+                    teleport_evw.send(Teleport {
+                        entity: event.entity,
+                        position: *position,
+                    });
+                }
+                TriggerAction::ChangeLevel {
+                    level: level_name,
+                    position,
+                } => match levels.levels.get(level_name) {
+                    Some(level) => {
+                        change_level_evw.send(ChangeLevel {
+                            level: level.clone(),
+                            position: *position,
+                            direction: None,
+                        });
+                    }
+                    None => {
+                        level_error_evw.send(LevelError::UnknownLevel(level_name.clone()));
+                    }
+                },
+            }
+        }
+    }
+}
@@ -1,13 +1,45 @@
 #![allow(clippy::type_complexity)]
 
+mod audio;
+mod camera;
+mod campaign;
+mod condition;
 mod controls;
+mod dialogue;
+mod flags;
+mod health;
+mod hud;
+mod inventory;
+mod item;
+mod journal;
 mod level;
 mod loading;
+mod localization;
+mod loot;
 mod menu;
+mod message_log;
 mod movement;
+mod save;
+mod settings;
+mod toast;
+mod travel;
 
+use crate::audio::InternalAudioPlugin;
+use crate::camera::CameraEffectsPlugin;
+use crate::campaign::CampaignPlugin;
+use crate::dialogue::DialoguePlugin;
+use crate::flags::FlagsPlugin;
+use crate::health::HealthPlugin;
+use crate::hud::HudPlugin;
+use crate::item::ItemPlugin;
+use crate::journal::JournalPlugin;
 use crate::loading::LoadingPlugin;
+use crate::localization::LocalizationPlugin;
+use crate::loot::LootPlugin;
 use crate::menu::MenuPlugin;
+use crate::message_log::MessageLogPlugin;
+use crate::save::SavePlugin;
+use crate::settings::SettingsPlugin;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
@@ -23,6 +55,8 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use controls::ControlsPlugin;
 use level::LevelPlugin;
 use movement::MovementPlugin;
+use toast::ToastPlugin;
+use travel::TravelPlugin;
 
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
@@ -36,20 +70,54 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // The game is frozen while the pause menu is drawn on top of it
+    Paused,
+}
+
+/// Windows that take the input while playing, the game keeps running beneath them
+#[derive(SubStates, Default, Clone, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Playing)]
+enum Modal {
+    #[default]
+    None,
+    // A sign, scroll or inscription is read
+    Reading,
+    // The player talks to an NPC
+    Dialogue,
 }
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<GameState>().add_plugins((
-            LoadingPlugin,
-            MenuPlugin,
-            LevelPlugin,
-            MovementPlugin,
-            ControlsPlugin,
-            EasingsPlugin,
-        ));
+        app.init_state::<GameState>()
+            .add_sub_state::<Modal>()
+            .add_plugins((
+                LoadingPlugin,
+                SettingsPlugin,
+                CampaignPlugin,
+                MenuPlugin,
+                LevelPlugin,
+                MovementPlugin,
+                CameraEffectsPlugin,
+                InternalAudioPlugin,
+                HealthPlugin,
+                ControlsPlugin,
+                TravelPlugin,
+                EasingsPlugin,
+                ToastPlugin,
+            ))
+            .add_plugins((
+                LocalizationPlugin,
+                HudPlugin,
+                MessageLogPlugin,
+                JournalPlugin,
+                FlagsPlugin,
+                DialoguePlugin,
+                ItemPlugin,
+                LootPlugin,
+                SavePlugin,
+            ));
 
         #[cfg(debug_assertions)]
         {
@@ -1,4 +1,12 @@
-use crate::{level::Level, GameState};
+use crate::{
+    campaign::Campaign,
+    dialogue::Dialogue,
+    item::ItemDatabase,
+    level::{Level, Script},
+    localization::TextTable,
+    loot::LootTable,
+    GameState,
+};
 use bevy::{prelude::*, utils::HashMap};
 use bevy_asset_loader::prelude::*;
 
@@ -14,7 +22,14 @@ impl Plugin for LoadingPlugin {
                 .continue_to_state(GameState::Menu)
                 .load_collection::<TextureAssets>()
                 .load_collection::<SceneAssets>()
-                .load_collection::<LevelAssets>(),
+                .load_collection::<AudioAssets>()
+                .load_collection::<LevelAssets>()
+                .load_collection::<CampaignAssets>()
+                .load_collection::<TextTableAssets>()
+                .load_collection::<DialogueAssets>()
+                .load_collection::<ScriptAssets>()
+                .load_collection::<ItemAssets>()
+                .load_collection::<LootTableAssets>(),
         );
     }
 }
@@ -30,12 +45,77 @@ pub struct TextureAssets {
     pub github: Handle<Image>,
 }
 
+#[derive(Debug, AssetCollection, Resource)]
+pub struct AudioAssets {
+    #[asset(path = "audio/footstep.wav")]
+    pub footstep: Handle<AudioSource>,
+    #[asset(path = "audio/bump.wav")]
+    pub bump: Handle<AudioSource>,
+    #[asset(path = "audio/chest.wav")]
+    pub chest: Handle<AudioSource>,
+    #[asset(path = "audio/door.wav")]
+    pub door: Handle<AudioSource>,
+    #[asset(path = "audio/page.wav")]
+    pub page: Handle<AudioSource>,
+    #[asset(path = "audio/lever.wav")]
+    pub lever: Handle<AudioSource>,
+    #[asset(path = "audio/teleporter.wav")]
+    pub teleporter: Handle<AudioSource>,
+    #[asset(path = "audio/torch.wav")]
+    pub torch: Handle<AudioSource>,
+    #[asset(path = "audio/water.wav")]
+    pub water: Handle<AudioSource>,
+    /// Ambient loops, levels refer to them by path
+    #[asset(path = "audio/ambience", collection(typed, mapped))]
+    pub ambience: HashMap<String, Handle<AudioSource>>,
+}
+
 #[derive(Debug, AssetCollection, Resource)]
 pub struct LevelAssets {
     #[asset(path = "level", collection(typed, mapped))]
     pub levels: HashMap<String, Handle<Level>>,
 }
 
+#[derive(Debug, AssetCollection, Resource)]
+pub struct CampaignAssets {
+    #[asset(path = "campaign", collection(typed, mapped))]
+    pub campaigns: HashMap<String, Handle<Campaign>>,
+}
+
+/// Text tables by path, e.g. `text/en.texts`
+#[derive(Debug, AssetCollection, Resource)]
+pub struct TextTableAssets {
+    #[asset(path = "text", collection(typed, mapped))]
+    pub tables: HashMap<String, Handle<TextTable>>,
+}
+
+/// Dialogues by path, e.g. `dialogue/gatekeeper.dialogue`
+#[derive(Debug, AssetCollection, Resource)]
+pub struct DialogueAssets {
+    #[asset(path = "dialogue", collection(typed, mapped))]
+    pub dialogues: HashMap<String, Handle<Dialogue>>,
+}
+
+#[derive(Debug, AssetCollection, Resource)]
+pub struct ItemAssets {
+    #[asset(path = "item/items.items")]
+    pub database: Handle<ItemDatabase>,
+}
+
+/// Loot tables by path, e.g. `loot/common.loot`
+#[derive(Debug, AssetCollection, Resource)]
+pub struct LootTableAssets {
+    #[asset(path = "loot", collection(typed, mapped))]
+    pub tables: HashMap<String, Handle<LootTable>>,
+}
+
+/// Level scripts by path, e.g. `script/000.rhai`
+#[derive(Debug, AssetCollection, Resource)]
+pub struct ScriptAssets {
+    #[asset(path = "script", collection(typed, mapped))]
+    pub scripts: HashMap<String, Handle<Script>>,
+}
+
 #[derive(Debug, AssetCollection, Resource)]
 pub struct SceneAssets {
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/chest.glb#Scene0")]
@@ -43,13 +123,13 @@ pub struct SceneAssets {
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/floor_tile_large.gltf.glb#Scene0")]
     floor_tile: Handle<Scene>,
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/key.gltf.glb#Scene0")]
-    _key: Handle<Scene>,
+    key: Handle<Scene>,
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/torch_mounted.gltf.glb#Scene0")]
-    _torch: Handle<Scene>,
+    torch: Handle<Scene>,
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/wall.gltf.glb#Scene0")]
     wall: Handle<Scene>,
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/wall_doorway.glb#Scene0")]
-    _door: Handle<Scene>,
+    doorway: Handle<Scene>,
     #[asset(path = "models/KayKit_DungeonRemastered_1.0_FREE/pillar_decorated.gltf.glb#Scene0")]
     pillar: Handle<Scene>,
 }
@@ -83,6 +163,110 @@ impl SceneAssets {
         }
     }
 
+    pub fn torch(&self, transform: Transform) -> SceneBundle {
+        SceneBundle {
+            scene: self.torch.clone(),
+            transform: transform.with_scale(Vec3::splat(8.0)),
+            ..Default::default()
+        }
+    }
+
+    /// The frame of a door, it is passable when the leaf is hidden
+    pub fn doorway(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -16.0, 0.0);
+        SceneBundle {
+            scene: self.doorway.clone(),
+            transform: transform.with_scale(Vec3::splat(8.0)),
+            ..Default::default()
+        }
+    }
+
+    /// The closed leaf filling the opening of the doorway, relative to it
+    pub fn door_leaf(&self) -> SceneBundle {
+        SceneBundle {
+            scene: self.wall.clone(),
+            transform: Transform::from_scale(Vec3::new(0.5, 0.75, 0.5)),
+            ..Default::default()
+        }
+    }
+
+    pub fn lever(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -12.0, 0.0);
+        SceneBundle {
+            scene: self.torch.clone(),
+            transform: transform
+                .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_4))
+                .with_scale(Vec3::splat(8.0)),
+            ..Default::default()
+        }
+    }
+
+    pub fn pressure_plate(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -15.5, 0.0);
+        SceneBundle {
+            scene: self.floor_tile.clone(),
+            transform: transform.with_scale(Vec3::new(3.0, 8.0, 3.0)),
+            ..Default::default()
+        }
+    }
+
+    /// Marks a detected trap on the floor
+    pub fn trap(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -15.5, 0.0);
+        SceneBundle {
+            scene: self.floor_tile.clone(),
+            transform: transform
+                .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4))
+                .with_scale(Vec3::new(2.5, 8.0, 2.5)),
+            ..Default::default()
+        }
+    }
+
+    pub fn sign(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -16.0, 0.0);
+        SceneBundle {
+            scene: self.pillar.clone(),
+            transform: transform.with_scale(Vec3::new(3.0, 4.0, 3.0)),
+            ..Default::default()
+        }
+    }
+
+    pub fn scroll(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -15.0, 0.0);
+        SceneBundle {
+            scene: self.key.clone(),
+            transform: transform.with_scale(Vec3::splat(8.0)),
+            ..Default::default()
+        }
+    }
+
+    /// A slab of wall standing in the cell
+    pub fn inscription(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -16.0, 0.0);
+        SceneBundle {
+            scene: self.wall.clone(),
+            transform: transform.with_scale(Vec3::new(6.0, 6.0, 2.0)),
+            ..Default::default()
+        }
+    }
+
+    pub fn npc(&self, transform: Transform) -> SceneBundle {
+        let mut transform = transform;
+        transform.translation += Vec3::new(0.0, -16.0, 0.0);
+        SceneBundle {
+            scene: self.pillar.clone(),
+            transform: transform.with_scale(Vec3::new(4.0, 6.0, 4.0)),
+            ..Default::default()
+        }
+    }
+
     pub fn pillar(&self, transform: Transform) -> SceneBundle {
         let mut transform = transform;
         transform.translation += Vec3::new(0.0, -16.0, 0.0);
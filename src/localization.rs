@@ -0,0 +1,102 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypePath,
+    utils::HashMap,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    loading::TextTableAssets,
+    settings::{Language, Settings},
+};
+
+pub struct LocalizationPlugin;
+
+/// This plugin loads [`TextTable`]s, so levels can refer to texts by key instead of spelling them out
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TextTable>()
+            .init_asset_loader::<TextTableAssetLoader>();
+    }
+}
+
+/// Texts of one language by their key
+#[derive(Debug, Default, Deserialize, Asset, TypePath, Deref)]
+#[serde(transparent)]
+pub struct TextTable(HashMap<String, String>);
+
+/// Text written into the level file or looked up in the text table of the chosen language
+#[derive(Debug, Clone, Deserialize)]
+pub enum LocalizedText {
+    Text(String),
+    Key(String),
+}
+
+/// Resolves [`LocalizedText`]s in the language from the [`Settings`]
+#[derive(SystemParam)]
+pub struct Texts<'w> {
+    settings: Res<'w, Settings>,
+    text_table_assets: Res<'w, TextTableAssets>,
+    text_tables: Res<'w, Assets<TextTable>>,
+}
+
+impl Texts<'_> {
+    fn table(&self, language: Language) -> Option<&TextTable> {
+        self.text_table_assets
+            .tables
+            .get(&format!("text/{}.texts", language.code()))
+            .and_then(|handle| self.text_tables.get(handle))
+    }
+
+    /// Falls back to English and then to the key itself, so missing texts stand out
+    pub fn get(&self, text: &LocalizedText) -> String {
+        match text {
+            LocalizedText::Text(text) => text.clone(),
+            LocalizedText::Key(key) => [self.settings.language, Language::English]
+                .into_iter()
+                .find_map(|language| self.table(language)?.get(key))
+                .cloned()
+                .unwrap_or_else(|| {
+                    warn!("There is no text for the key {key}");
+                    key.clone()
+                }),
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum TextTableAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+#[derive(Default)]
+pub struct TextTableAssetLoader;
+
+impl AssetLoader for TextTableAssetLoader {
+    type Asset = TextTable;
+    type Settings = ();
+    type Error = TextTableAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes::<TextTable>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["texts"]
+    }
+}
@@ -0,0 +1,514 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypePath,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    health::Defeated,
+    item::{ItemId, PickUp},
+    level::{CurrentLevel, LevelError, LevelStates, Loot, Player},
+    loading::LootTableAssets,
+    movement::GridPosition,
+    GameState,
+};
+
+pub struct LootPlugin;
+
+/// This plugin rolls [`LootTable`]s, with the [`LootSeed`] of the running game
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LootTable>()
+            .init_asset_loader::<LootTableAssetLoader>()
+            .init_resource::<LootSeed>()
+            .add_systems(Update, drop_enemy_loot.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Weighted random drops, written in `.loot` files and referred to by path, e.g. `loot/common.loot`
+#[derive(Debug, Deserialize, Asset, TypePath)]
+pub struct LootTable {
+    /// Always dropped, on top of the rolls
+    #[serde(default)]
+    pub guaranteed: Vec<LootDrop>,
+    /// How many entries are drawn
+    #[serde(default = "LootTable::default_rolls")]
+    pub rolls: Quantity,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+impl LootTable {
+    fn default_rolls() -> Quantity {
+        Quantity::Exactly(1)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LootEntry {
+    /// Entries are drawn in proportion to their weight
+    pub weight: u32,
+    pub drop: LootDrop,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum LootDrop {
+    Item {
+        item: ItemId,
+        #[serde(default = "LootDrop::default_count")]
+        count: Quantity,
+    },
+    /// Rolls another table
+    Table(String),
+    Nothing,
+}
+
+impl LootDrop {
+    fn default_count() -> Quantity {
+        Quantity::Exactly(1)
+    }
+}
+
+/// Written as `2` or as the inclusive range `(1, 3)`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum Quantity {
+    Exactly(u32),
+    Between(u32, u32),
+}
+
+impl Quantity {
+    fn roll(&self, rng: &mut LootRng) -> u32 {
+        match *self {
+            Quantity::Exactly(count) => count,
+            /* In u64, so the range of (0, u32::MAX) does not overflow */
+            Quantity::Between(min, max) => min + rng.below(u64::from(max - min) + 1) as u32,
+        }
+    }
+}
+
+/// Seed of the loot of the running game, the same seed drops the same loot
+///
+/// Chests roll with a seed derived from their level and position, so the order they are opened in does not matter.
+/// It is chosen when a game starts, see [`NewGame`](crate::level::NewGame), and kept in saved games
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LootSeed(pub u64);
+
+impl LootSeed {
+    /// The seed comes from how long the game runs, SystemTime is not available on the web
+    pub fn from_time(time: &Time<Real>) -> Self {
+        Self(LootRng(time.elapsed().as_nanos() as u64).next_u64())
+    }
+
+    fn rng(&self, level: &str, position: GridPosition) -> LootRng {
+        /* FNV-1a, unlike the std hasher it is stable between Rust versions */
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for byte in level.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        let mut rng = LootRng(self.0 ^ hash);
+        rng.0 ^= rng.next_u64() ^ position.x as u64;
+        rng.0 ^= rng.next_u64() ^ position.y as u64;
+        rng
+    }
+}
+
+/// SplitMix64, small and with the same output on every platform
+#[derive(Debug, Clone)]
+struct LootRng(u64);
+
+impl LootRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`, zero for an empty range
+    fn below(&mut self, bound: u64) -> u64 {
+        match bound {
+            0 => 0,
+            bound => self.next_u64() % bound,
+        }
+    }
+}
+
+/// Nested tables deeper than this are assumed to refer to themselves
+const MAX_DEPTH: usize = 16;
+
+/// Rolls [`LootTable`]s by their path, with the [`LootSeed`] of the game
+#[derive(SystemParam)]
+pub struct LootTables<'w> {
+    loot_table_assets: Res<'w, LootTableAssets>,
+    tables: Res<'w, Assets<LootTable>>,
+    loot_seed: Res<'w, LootSeed>,
+}
+
+impl LootTables<'_> {
+    /// The loot of a fixed place like a chest, the same place always drops the same.
+    /// Equal items are merged
+    pub fn roll_at(
+        &self,
+        path: &str,
+        level: &str,
+        position: GridPosition,
+    ) -> Result<Vec<Loot>, LevelError> {
+        self.roll(path, &mut self.loot_seed.rng(level, position))
+    }
+
+    /// The loot of an enemy, seeded like [`LootTables::roll_at`] and by how many enemies of the level were defeated before,
+    /// so enemies dying on the same cell drop their own
+    pub fn roll_for_enemy(
+        &self,
+        path: &str,
+        level: &str,
+        position: GridPosition,
+        defeated_before: u64,
+    ) -> Result<Vec<Loot>, LevelError> {
+        let mut rng = self.loot_seed.rng(level, position);
+        rng.0 ^= rng.next_u64() ^ defeated_before;
+        self.roll(path, &mut rng)
+    }
+
+    fn roll(&self, path: &str, rng: &mut LootRng) -> Result<Vec<Loot>, LevelError> {
+        let mut loot = Vec::new();
+        self.roll_into(path, rng, 0, &mut loot)?;
+        Ok(loot)
+    }
+
+    fn roll_into(
+        &self,
+        path: &str,
+        rng: &mut LootRng,
+        depth: usize,
+        loot: &mut Vec<Loot>,
+    ) -> Result<(), LevelError> {
+        if depth > MAX_DEPTH {
+            return Err(LevelError::LootTableTooDeep(path.to_string()));
+        }
+        let Some(table) = self
+            .loot_table_assets
+            .tables
+            .get(path)
+            .and_then(|handle| self.tables.get(handle))
+        else {
+            return Err(LevelError::UnknownLootTable(path.to_string()));
+        };
+
+        for drop in &table.guaranteed {
+            self.drop_into(drop, rng, depth, loot)?;
+        }
+
+        let total = table
+            .entries
+            .iter()
+            .map(|entry| u64::from(entry.weight))
+            .sum::<u64>();
+        for _ in 0..table.rolls.roll(rng) {
+            let mut pick = rng.below(total);
+            /* Entries without weight are never picked */
+            let Some(entry) = table.entries.iter().find(|entry| {
+                if pick < u64::from(entry.weight) {
+                    return true;
+                }
+                pick -= u64::from(entry.weight);
+                false
+            }) else {
+                continue;
+            };
+            self.drop_into(&entry.drop, rng, depth, loot)?;
+        }
+        Ok(())
+    }
+
+    fn drop_into(
+        &self,
+        drop: &LootDrop,
+        rng: &mut LootRng,
+        depth: usize,
+        loot: &mut Vec<Loot>,
+    ) -> Result<(), LevelError> {
+        match drop {
+            LootDrop::Item { item, count } => {
+                let count = count.roll(rng);
+                if count == 0 {
+                    return Ok(());
+                }
+                match loot.iter_mut().find(|loot| loot.item == *item) {
+                    Some(loot) => loot.count = loot.count.saturating_add(count),
+                    None => loot.push(Loot {
+                        item: item.clone(),
+                        count,
+                    }),
+                }
+                Ok(())
+            }
+            LootDrop::Table(path) => self.roll_into(path, rng, depth + 1, loot),
+            LootDrop::Nothing => Ok(()),
+        }
+    }
+}
+
+/// Enemies with this drop loot from the table at the path, when they are defeated
+#[derive(Debug, Clone, Component, Deserialize)]
+pub struct DropsLoot(pub String);
+
+/// The loot goes straight to the players
+#[allow(clippy::too_many_arguments)]
+fn drop_enemy_loot(
+    mut defeated_evr: EventReader<Defeated>,
+    loot_tables: LootTables,
+    current_level: Res<CurrentLevel>,
+    mut level_states: ResMut<LevelStates>,
+    enemies: Query<(&DropsLoot, &GridPosition)>,
+    players: Query<Entity, With<Player>>,
+    mut pick_up_evw: EventWriter<PickUp>,
+    mut level_error_evw: EventWriter<LevelError>,
+) {
+    for event in defeated_evr.read() {
+        let Ok((drops_loot, position)) = enemies.get(event.entity) else {
+            continue;
+        };
+
+        /* The count is part of the level state, so a continued game drops the same */
+        let level_state = level_states.get_mut(&current_level.0);
+        let defeated_before = level_state.defeated_enemies;
+        level_state.defeated_enemies += 1;
+
+        let level = current_level.path().map(ToString::to_string);
+        match loot_tables.roll_for_enemy(
+            &drops_loot.0,
+            &level.unwrap_or_default(),
+            *position,
+            defeated_before,
+        ) {
+            Ok(loot) => {
+                for player in &players {
+                    pick_up_evw.send_batch(loot.iter().map(|loot| PickUp {
+                        entity: player,
+                        item: loot.item.clone(),
+                        count: loot.count,
+                    }));
+                }
+            }
+            Err(error) => {
+                level_error_evw.send(error);
+            }
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LootTableAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+    /// A range that starts after it ends
+    #[error("The quantity ({0}, {1}) is empty")]
+    EmptyQuantity(u32, u32),
+}
+
+#[derive(Default)]
+pub struct LootTableAssetLoader;
+
+impl AssetLoader for LootTableAssetLoader {
+    type Asset = LootTable;
+    type Settings = ();
+    type Error = LootTableAssetLoaderError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let custom_asset = ron::de::from_bytes::<LootTable>(&bytes)?;
+
+        let counts = custom_asset
+            .guaranteed
+            .iter()
+            .chain(custom_asset.entries.iter().map(|entry| &entry.drop))
+            .filter_map(|drop| match drop {
+                LootDrop::Item { count, .. } => Some(count),
+                _ => None,
+            });
+        for quantity in std::iter::once(&custom_asset.rolls).chain(counts) {
+            if let Quantity::Between(min, max) = *quantity {
+                if min > max {
+                    return Err(LootTableAssetLoaderError::EmptyQuantity(min, max));
+                }
+            }
+        }
+        Ok(custom_asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["loot"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, utils::HashMap};
+
+    use super::*;
+
+    const TABLE: &str = r#"(
+        rolls: (1, 4),
+        entries: [
+            (weight: 5, drop: Item(item: "coin", count: (1, 20))),
+            (weight: 3, drop: Item(item: "torch")),
+            (weight: 1, drop: Item(item: "key")),
+            (weight: 2, drop: Nothing),
+        ],
+    )"#;
+
+    /* Together the weights do not fit into a u32 */
+    const HEAVY_TABLE: &str = r#"(
+        rolls: 64,
+        entries: [
+            (weight: 4294967295, drop: Nothing),
+            (weight: 4294967295, drop: Item(item: "coin", count: (0, 4294967295))),
+        ],
+    )"#;
+
+    fn app(seed: u64) -> App {
+        let mut app = App::new();
+        app.init_resource::<Assets<LootTable>>()
+            .insert_resource(LootSeed(seed));
+
+        let mut tables = HashMap::default();
+        for (path, table) in [("loot/test.loot", TABLE), ("loot/heavy.loot", HEAVY_TABLE)] {
+            let table = ron::de::from_str::<LootTable>(table).unwrap();
+            let handle = app
+                .world_mut()
+                .resource_mut::<Assets<LootTable>>()
+                .add(table);
+            tables.insert(path.to_string(), handle);
+        }
+        app.insert_resource(LootTableAssets { tables });
+        app
+    }
+
+    fn roll(app: &mut App, level: &'static str, x: usize, y: usize) -> Vec<(String, u32)> {
+        roll_table(app, "loot/test.loot", level, x, y)
+    }
+
+    fn roll_table(
+        app: &mut App,
+        path: &'static str,
+        level: &'static str,
+        x: usize,
+        y: usize,
+    ) -> Vec<(String, u32)> {
+        app.world_mut()
+            .run_system_once(move |loot_tables: LootTables| {
+                loot_tables
+                    .roll_at(path, level, GridPosition { x, y })
+                    .unwrap()
+                    .into_iter()
+                    .map(|loot| (loot.item.0, loot.count))
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn the_same_place_drops_the_same() {
+        let mut app = app(42);
+        let first = roll(&mut app, "level/000.lvl", 3, 4);
+
+        /* Rolling elsewhere in between does not change it */
+        roll(&mut app, "level/000.lvl", 4, 3);
+        roll(&mut app, "level/001.lvl", 3, 4);
+        assert_eq!(roll(&mut app, "level/000.lvl", 3, 4), first);
+
+        let mut new_game = self::app(42);
+        assert_eq!(roll(&mut new_game, "level/000.lvl", 3, 4), first);
+    }
+
+    #[test]
+    fn the_seed_changes_the_drops() {
+        let rolls = |seed| {
+            let mut app = app(seed);
+            (0..8)
+                .map(|x| roll(&mut app, "level/000.lvl", x, 0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(1), rolls(1));
+        assert_ne!(rolls(1), rolls(2));
+    }
+
+    #[test]
+    fn wide_quantities_do_not_overflow() {
+        let mut rng = LootRng(7);
+        for _ in 0..100 {
+            Quantity::Between(0, u32::MAX).roll(&mut rng);
+            let count = Quantity::Between(u32::MAX - 1, u32::MAX).roll(&mut rng);
+            assert!(count >= u32::MAX - 1);
+        }
+    }
+
+    #[test]
+    fn heavy_weights_do_not_overflow() {
+        let loot = roll_table(&mut app(42), "loot/heavy.loot", "level/000.lvl", 0, 0);
+        /* Both entries weigh the same, so some of the rolls drop coins */
+        assert!(loot.iter().all(|(item, _)| item == "coin"));
+        assert!(!loot.is_empty());
+    }
+
+    /// Defeats `count` enemies on the same cell, one after another, and returns what each dropped
+    fn enemy_drops(seed: u64, count: usize) -> Vec<Vec<(String, u32)>> {
+        let mut app = app(seed);
+        app.add_event::<Defeated>()
+            .add_event::<PickUp>()
+            .add_event::<LevelError>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<LevelStates>()
+            .add_systems(Update, drop_enemy_loot);
+        app.world_mut().spawn(Player);
+
+        let drops = (0..count)
+            .map(|_| {
+                let entity = app
+                    .world_mut()
+                    .spawn((
+                        DropsLoot("loot/test.loot".to_string()),
+                        GridPosition { x: 3, y: 4 },
+                    ))
+                    .id();
+                app.world_mut().send_event(Defeated { entity });
+                app.update();
+
+                let mut pick_ups = app.world_mut().resource_mut::<Events<PickUp>>();
+                pick_ups
+                    .drain()
+                    .map(|pick_up| (pick_up.item.0, pick_up.count))
+                    .collect()
+            })
+            .collect();
+
+        let level_states = app.world().resource::<LevelStates>();
+        let level_state = level_states.get(&CurrentLevel::default().0).unwrap();
+        assert_eq!(level_state.defeated_enemies, count as u64);
+        drops
+    }
+
+    #[test]
+    fn enemies_drop_their_own_loot() {
+        let drops = enemy_drops(42, 8);
+        assert_eq!(enemy_drops(42, 8), drops);
+        /* Enemies defeated on the same cell do not all drop the same */
+        assert!(drops.iter().any(|drop| *drop != drops[0]));
+    }
+}
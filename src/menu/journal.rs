@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use crate::{journal::Journal, localization::Texts};
+
+use super::{
+    pause::{ChangePauseScreen, PauseScreen},
+    spawn_button,
+};
+
+pub struct JournalMenuPlugin;
+
+/// This plugin draws the journal screen of the pause menu, listing everything the player has read
+impl Plugin for JournalMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseScreen::Journal), setup_journal)
+            .add_systems(
+                OnExit(PauseScreen::Journal),
+                super::cleanup_screen::<JournalScreen>,
+            )
+            .add_systems(
+                Update,
+                show_journal_entry.run_if(in_state(PauseScreen::Journal)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct JournalScreen;
+
+#[derive(Component)]
+struct ShowJournalEntry(usize);
+
+#[derive(Component)]
+struct JournalEntryText;
+
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+fn setup_journal(mut commands: Commands, journal: Res<Journal>, texts: Texts) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            JournalScreen,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Journal",
+                TextStyle {
+                    font_size: 50.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Percent(60.0),
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    /* Titles on the left, the selected entry on the right */
+                    row.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            overflow: Overflow::clip_y(),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|list| {
+                        for (index, entry) in journal.entries().iter().enumerate().rev() {
+                            spawn_button(
+                                list,
+                                &entry.readable.title(&texts),
+                                ShowJournalEntry(index),
+                            );
+                        }
+                    });
+
+                    let placeholder = if journal.entries().is_empty() {
+                        "You have not read anything yet"
+                    } else {
+                        "Choose an entry to read it again"
+                    };
+                    row.spawn((
+                        TextBundle::from_section(
+                            placeholder,
+                            TextStyle {
+                                font_size: 20.0,
+                                color: TEXT_COLOR,
+                                ..default()
+                            },
+                        )
+                        .with_style(Style {
+                            width: Val::Px(500.0),
+                            ..default()
+                        }),
+                        JournalEntryText,
+                    ));
+                });
+
+            spawn_button(children, "Back", ChangePauseScreen(PauseScreen::Main));
+        });
+}
+
+fn show_journal_entry(
+    journal: Res<Journal>,
+    texts: Texts,
+    buttons: Query<(&Interaction, &ShowJournalEntry), Changed<Interaction>>,
+    mut entry_texts: Query<&mut Text, With<JournalEntryText>>,
+) {
+    for (interaction, show_entry) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(entry) = journal.entries().get(show_entry.0) else {
+            continue;
+        };
+
+        for mut text in &mut entry_texts {
+            text.sections[0].value = texts.get(&entry.readable.text);
+        }
+    }
+}
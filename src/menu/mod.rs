@@ -0,0 +1,466 @@
+mod journal;
+mod pause;
+mod settings;
+
+use crate::campaign::{level_path, ActiveCampaign, Campaign, CampaignProgress};
+use crate::level::{Level, NewGame};
+use crate::loading::{CampaignAssets, LevelAssets, TextureAssets};
+use crate::save::SavedGame;
+use crate::GameState;
+use bevy::prelude::*;
+
+use self::journal::JournalMenuPlugin;
+use self::pause::{PausePlugin, PauseScreen};
+use self::settings::SettingsMenuPlugin;
+
+pub struct MenuPlugin;
+
+/// This plugin is responsible for the game menu
+/// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((PausePlugin, SettingsMenuPlugin, JournalMenuPlugin))
+            .add_sub_state::<MenuScreen>()
+            .add_systems(OnEnter(GameState::Menu), setup_menu_camera)
+            .add_systems(OnEnter(MenuScreen::Title), setup_menu)
+            .add_systems(OnExit(MenuScreen::Title), cleanup_screen::<TitleScreen>)
+            .add_systems(OnEnter(MenuScreen::LevelSelect), setup_level_select)
+            .add_systems(
+                OnExit(MenuScreen::LevelSelect),
+                cleanup_screen::<LevelSelectScreen>,
+            )
+            .add_systems(
+                Update,
+                click_play_button
+                    .run_if(in_state(GameState::Menu).or_else(in_state(GameState::Paused))),
+            )
+            .add_systems(OnExit(GameState::Menu), cleanup_menu);
+    }
+}
+
+/// The screens of the menu, only existing during `GameState::Menu`
+#[derive(SubStates, Default, Clone, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Menu)]
+enum MenuScreen {
+    #[default]
+    Title,
+    LevelSelect,
+    Settings,
+}
+
+#[derive(Component)]
+struct ButtonColors {
+    normal: Color,
+    hovered: Color,
+}
+
+impl Default for ButtonColors {
+    fn default() -> Self {
+        ButtonColors {
+            normal: Color::srgb(0.15, 0.15, 0.15),
+            hovered: Color::srgb(0.25, 0.25, 0.25),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Menu;
+
+#[derive(Component)]
+struct TitleScreen;
+
+#[derive(Component)]
+struct LevelSelectScreen;
+
+fn setup_menu_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default()).insert(Menu);
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    active_campaign: Res<ActiveCampaign>,
+    campaigns: Res<Assets<Campaign>>,
+) {
+    info!("menu");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            TitleScreen,
+        ))
+        .with_children(|children| {
+            if let Some(campaign) = campaigns.get(&active_campaign.0) {
+                children.spawn(TextBundle::from_section(
+                    campaign.title.clone(),
+                    TextStyle {
+                        font_size: 50.0,
+                        color: Color::srgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ));
+                children.spawn(
+                    TextBundle::from_section(
+                        campaign.description.clone(),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::srgb(0.7, 0.7, 0.7),
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(20.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(140.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Playing),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Play",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+            if let Some(saved_game) = SavedGame::load() {
+                spawn_button(children, "Continue", ContinueGame(saved_game));
+            }
+            spawn_button(
+                children,
+                "Select Level",
+                ChangeScreen(MenuScreen::LevelSelect),
+            );
+            spawn_button(children, "Settings", ChangeScreen(MenuScreen::Settings));
+        });
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceAround,
+                    bottom: Val::Px(5.),
+                    width: Val::Percent(100.),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            TitleScreen,
+        ))
+        .with_children(|children| {
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(170.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::SpaceAround,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(5.)),
+                            ..Default::default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..Default::default()
+                    },
+                    ButtonColors {
+                        normal: Color::NONE,
+                        ..default()
+                    },
+                    OpenLink("https://bevyengine.org"),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Made with Bevy",
+                        TextStyle {
+                            font_size: 15.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn(ImageBundle {
+                        image: textures.bevy.clone().into(),
+                        style: Style {
+                            width: Val::Px(32.),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                });
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(170.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::SpaceAround,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(5.)),
+                            ..default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..Default::default()
+                    },
+                    ButtonColors {
+                        normal: Color::NONE,
+                        hovered: Color::srgb(0.25, 0.25, 0.25),
+                    },
+                    OpenLink("https://github.com/NiklasEi/bevy_game_template"),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Open source",
+                        TextStyle {
+                            font_size: 15.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn(ImageBundle {
+                        image: textures.github.clone().into(),
+                        style: Style {
+                            width: Val::Px(32.),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                });
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, action: impl Bundle) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::horizontal(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::srgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn setup_level_select(
+    mut commands: Commands,
+    campaign_assets: Res<CampaignAssets>,
+    level_assets: Res<LevelAssets>,
+    campaigns: Res<Assets<Campaign>>,
+    progress: Res<CampaignProgress>,
+) {
+    let mut campaign_handles = campaign_assets.campaigns.iter().collect::<Vec<_>>();
+    campaign_handles.sort_by_key(|(path, _)| *path);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LevelSelectScreen,
+        ))
+        .with_children(|children| {
+            for (_, campaign_handle) in campaign_handles {
+                let Some(campaign) = campaigns.get(campaign_handle) else {
+                    continue;
+                };
+
+                let completed = if progress.is_campaign_completed(campaign_handle) {
+                    " (completed)"
+                } else {
+                    ""
+                };
+                children.spawn(
+                    TextBundle::from_section(
+                        format!("{}{completed}", campaign.title),
+                        TextStyle {
+                            font_size: 32.0,
+                            color: Color::srgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    }),
+                );
+
+                for level in &campaign.levels {
+                    let Some(level_handle) = level_assets.levels.get(&level.path) else {
+                        warn!(
+                            "Campaign {} has unknown level {}",
+                            campaign.title, level.path
+                        );
+                        continue;
+                    };
+
+                    let completed = if progress.is_level_completed(&level.path) {
+                        " (completed)"
+                    } else {
+                        ""
+                    };
+                    spawn_button(
+                        children,
+                        &format!("{}{completed}", level.title),
+                        StartLevel {
+                            campaign: campaign_handle.clone(),
+                            level: level_handle.clone(),
+                        },
+                    );
+                }
+            }
+
+            spawn_button(children, "Back", ChangeScreen(MenuScreen::Title));
+        });
+}
+
+#[derive(Component)]
+struct ChangeState(GameState);
+
+#[derive(Component)]
+struct ChangeScreen(MenuScreen);
+
+#[derive(Component)]
+struct OpenLink(&'static str);
+
+/// Continues the game that was saved last
+#[derive(Component)]
+struct ContinueGame(SavedGame);
+
+/// Starts a new game of `campaign` in `level`
+#[derive(Component)]
+struct StartLevel {
+    campaign: Handle<Campaign>,
+    level: Handle<Level>,
+}
+
+fn click_play_button(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut active_campaign: ResMut<ActiveCampaign>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&ChangeState>,
+            Option<&ChangeScreen>,
+            Option<&StartLevel>,
+            Option<&ContinueGame>,
+            Option<&OpenLink>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (
+        interaction,
+        mut color,
+        button_colors,
+        change_state,
+        change_screen,
+        start_level,
+        continue_game,
+        open_link,
+    ) in &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => {
+                if let Some(state) = change_state {
+                    next_state.set(state.0.clone());
+                } else if let Some(screen) = change_screen {
+                    next_screen.set(screen.0.clone());
+                } else if let Some(start_level) = start_level {
+                    info!("Starting in level {:?}", level_path(&start_level.level));
+                    active_campaign.0 = start_level.campaign.clone();
+                    commands.insert_resource(NewGame {
+                        level: start_level.level.clone(),
+                        seed: None,
+                    });
+                    next_state.set(GameState::Playing);
+                } else if let Some(continue_game) = continue_game {
+                    info!("Continuing in level {}", continue_game.0.level);
+                    commands.insert_resource(continue_game.0.clone());
+                    next_state.set(GameState::Playing);
+                } else if let Some(link) = open_link {
+                    if let Err(error) = webbrowser::open(link.0) {
+                        warn!("Failed to open link {error:?}");
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn cleanup_screen<T: Component>(mut commands: Commands, screen: Query<Entity, With<T>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy_easings::{EasingComponent, EasingState};
+
+use crate::{save::SaveGame, GameState, Modal};
+
+use super::{spawn_button, ChangeState};
+
+pub struct PausePlugin;
+
+/// This plugin freezes the game on Escape and draws the pause menu on top of it
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseScreen>()
+            .add_systems(OnEnter(GameState::Paused), pause_easings)
+            .add_systems(OnExit(GameState::Paused), resume_easings)
+            .add_systems(OnEnter(PauseScreen::Main), setup_pause_menu)
+            .add_systems(
+                OnExit(PauseScreen::Main),
+                super::cleanup_screen::<PauseMenu>,
+            )
+            .add_systems(Update, toggle_pause)
+            .add_systems(
+                Update,
+                (change_pause_screen, click_save_button).run_if(in_state(GameState::Paused)),
+            );
+    }
+}
+
+/// The screens of the pause menu, only existing during `GameState::Paused`
+#[derive(SubStates, Default, Clone, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Paused)]
+pub(super) enum PauseScreen {
+    #[default]
+    Main,
+    Settings,
+    Journal,
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct SaveButton;
+
+#[derive(Component)]
+pub(super) struct ChangePauseScreen(pub(super) PauseScreen);
+
+fn setup_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 50.0,
+                    color: Color::srgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+            spawn_button(children, "Resume", ChangeState(GameState::Playing));
+            spawn_button(children, "Save Game", SaveButton);
+            spawn_button(children, "Journal", ChangePauseScreen(PauseScreen::Journal));
+            spawn_button(
+                children,
+                "Settings",
+                ChangePauseScreen(PauseScreen::Settings),
+            );
+            spawn_button(children, "Quit to Title", ChangeState(GameState::Menu));
+        });
+}
+
+/// Escape pauses the game, and resumes it from the main pause screen.
+/// Open windows like the reading panel are closed by Escape instead.
+fn toggle_pause(
+    key_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    modal: Option<Res<State<Modal>>>,
+    pause_screen: Option<Res<State<PauseScreen>>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !key_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing if modal.is_some_and(|modal| *modal.get() == Modal::None) => {
+            next_state.set(GameState::Paused)
+        }
+        GameState::Paused
+            if pause_screen.is_some_and(|screen| *screen.get() == PauseScreen::Main) =>
+        {
+            next_state.set(GameState::Playing)
+        }
+        _ => {}
+    }
+}
+
+fn change_pause_screen(
+    buttons: Query<(&Interaction, &ChangePauseScreen), Changed<Interaction>>,
+    mut next_screen: ResMut<NextState<PauseScreen>>,
+) {
+    for (interaction, change_screen) in &buttons {
+        if *interaction == Interaction::Pressed {
+            next_screen.set(change_screen.0.clone());
+        }
+    }
+}
+
+fn click_save_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<SaveButton>)>,
+    mut save_game_evw: EventWriter<SaveGame>,
+) {
+    for interaction in &buttons {
+        if *interaction == Interaction::Pressed {
+            save_game_evw.send(SaveGame);
+        }
+    }
+}
+
+/* Movement only finishes while playing, so running easings are held until the game is resumed */
+fn pause_easings(mut easings: Query<&mut EasingComponent<Transform>>) {
+    for mut easing in &mut easings {
+        easing.state = EasingState::Paused;
+    }
+}
+
+fn resume_easings(mut easings: Query<&mut EasingComponent<Transform>>) {
+    for mut easing in &mut easings {
+        easing.state = EasingState::Play;
+    }
+}
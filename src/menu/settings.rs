@@ -0,0 +1,328 @@
+use bevy::{prelude::*, window::WindowMode};
+
+use crate::settings::{Action, Easing, Language, Settings};
+
+use super::{spawn_button, MenuScreen, PauseScreen};
+
+pub struct SettingsMenuPlugin;
+
+/// This plugin draws the settings screen, which can be opened from the title and the pause menu
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(OnEnter(MenuScreen::Settings), setup_settings)
+            .add_systems(OnEnter(PauseScreen::Settings), setup_settings)
+            .add_systems(OnExit(MenuScreen::Settings), save_settings)
+            .add_systems(OnExit(PauseScreen::Settings), save_settings)
+            .add_systems(
+                Update,
+                (
+                    adjust_settings,
+                    start_rebinding,
+                    rebind,
+                    leave_settings,
+                    update_setting_texts,
+                )
+                    .run_if(any_with_component::<SettingsScreen>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+/// Settings that are changed in steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    MoveDuration,
+    Easing,
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    FieldOfView,
+    CameraEffects,
+    WindowMode,
+    Language,
+}
+
+impl Setting {
+    const ALL: [Setting; 9] = [
+        Setting::MoveDuration,
+        Setting::Easing,
+        Setting::MasterVolume,
+        Setting::MusicVolume,
+        Setting::EffectsVolume,
+        Setting::FieldOfView,
+        Setting::CameraEffects,
+        Setting::WindowMode,
+        Setting::Language,
+    ];
+
+    const WINDOW_MODES: [WindowMode; 3] = [
+        WindowMode::Windowed,
+        WindowMode::BorderlessFullscreen,
+        WindowMode::Fullscreen,
+    ];
+
+    fn text(&self, settings: &Settings) -> String {
+        match self {
+            Setting::MoveDuration if settings.move_duration == 0 => {
+                "Movement duration: instant".to_string()
+            }
+            Setting::MoveDuration => format!("Movement duration: {} ms", settings.move_duration),
+            Setting::Easing => format!("Easing: {:?}", settings.easing),
+            Setting::MasterVolume => {
+                format!("Master volume: {:.0}%", settings.master_volume * 100.0)
+            }
+            Setting::MusicVolume => format!("Music volume: {:.0}%", settings.music_volume * 100.0),
+            Setting::EffectsVolume => {
+                format!("Effects volume: {:.0}%", settings.effects_volume * 100.0)
+            }
+            Setting::FieldOfView => format!("Field of view: {:.0}°", settings.field_of_view),
+            Setting::CameraEffects => format!(
+                "Camera effects: {}",
+                if settings.camera_effects { "on" } else { "off" }
+            ),
+            Setting::WindowMode => format!("Window mode: {:?}", settings.window_mode),
+            Setting::Language => format!("Language: {:?}", settings.language),
+        }
+    }
+
+    fn adjust(&self, settings: &mut Settings, step: i32) {
+        match self {
+            Setting::MoveDuration => {
+                settings.move_duration =
+                    (settings.move_duration as i64 + step as i64 * 50).clamp(0, 1000) as u64;
+            }
+            Setting::Easing => {
+                settings.easing = cycle(&Easing::ALL, &settings.easing, step);
+            }
+            Setting::MasterVolume => {
+                settings.master_volume = adjust_volume(settings.master_volume, step);
+            }
+            Setting::MusicVolume => {
+                settings.music_volume = adjust_volume(settings.music_volume, step);
+            }
+            Setting::EffectsVolume => {
+                settings.effects_volume = adjust_volume(settings.effects_volume, step);
+            }
+            Setting::FieldOfView => {
+                settings.field_of_view =
+                    (settings.field_of_view + step as f32 * 5.0).clamp(30.0, 120.0);
+            }
+            Setting::CameraEffects => {
+                settings.camera_effects = !settings.camera_effects;
+            }
+            Setting::WindowMode => {
+                settings.window_mode = cycle(&Self::WINDOW_MODES, &settings.window_mode, step);
+            }
+            Setting::Language => {
+                settings.language = cycle(&Language::ALL, &settings.language, step);
+            }
+        }
+    }
+}
+
+fn adjust_volume(volume: f32, step: i32) -> f32 {
+    ((volume * 10.0).round() + step as f32).clamp(0.0, 10.0) / 10.0
+}
+
+fn cycle<T: Copy + PartialEq>(values: &[T], current: &T, step: i32) -> T {
+    let index = values
+        .iter()
+        .position(|value| value == current)
+        .unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+#[derive(Component)]
+struct AdjustSetting(Setting, i32);
+
+#[derive(Component)]
+struct BindingText(Action);
+
+#[derive(Component)]
+struct Rebind(Action);
+
+/// The action that gets bound to the next key press
+#[derive(Default, Resource)]
+struct Rebinding(Option<Action>);
+
+#[derive(Component)]
+struct LeaveSettings;
+
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+fn setup_settings(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|children| {
+            for setting in Setting::ALL {
+                spawn_row(children, |row| {
+                    spawn_button(row, "<", AdjustSetting(setting, -1));
+                    row.spawn((text_bundle(setting.text(&settings)), SettingText(setting)));
+                    spawn_button(row, ">", AdjustSetting(setting, 1));
+                });
+            }
+
+            for action in Action::ALL {
+                spawn_row(children, |row| {
+                    row.spawn((
+                        text_bundle(binding_text(action, &settings, None)),
+                        BindingText(action),
+                    ));
+                    spawn_button(row, "Rebind", Rebind(action));
+                });
+            }
+
+            spawn_button(children, "Back", LeaveSettings);
+        });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, spawn_children: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(spawn_children);
+}
+
+fn text_bundle(text: String) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 24.0,
+            color: TEXT_COLOR,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        width: Val::Px(360.0),
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        ..default()
+    })
+}
+
+fn binding_text(action: Action, settings: &Settings, rebinding: Option<Action>) -> String {
+    if rebinding == Some(action) {
+        format!("{}: press a key...", action.label())
+    } else {
+        format!(
+            "{}: {:?}",
+            action.label(),
+            settings.key_bindings.key(action)
+        )
+    }
+}
+
+fn adjust_settings(
+    mut settings: ResMut<Settings>,
+    buttons: Query<(&Interaction, &AdjustSetting), Changed<Interaction>>,
+) {
+    for (interaction, adjust_setting) in &buttons {
+        if *interaction == Interaction::Pressed {
+            adjust_setting.0.adjust(&mut settings, adjust_setting.1);
+        }
+    }
+}
+
+fn start_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    buttons: Query<(&Interaction, &Rebind), Changed<Interaction>>,
+) {
+    for (interaction, rebind) in &buttons {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some(rebind.0);
+        }
+    }
+}
+
+fn rebind(
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    /* Escape cancels rebinding */
+    if let Some(key_code) = key_input.get_just_pressed().next() {
+        if *key_code != KeyCode::Escape {
+            settings.key_bindings.bind(action, *key_code);
+        }
+        rebinding.0 = None;
+    }
+}
+
+fn leave_settings(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<LeaveSettings>)>,
+    menu_screen: Option<Res<State<MenuScreen>>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut next_pause_screen: ResMut<NextState<PauseScreen>>,
+) {
+    for interaction in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if menu_screen.is_some() {
+            next_menu_screen.set(MenuScreen::Title);
+        } else {
+            next_pause_screen.set(PauseScreen::Main);
+        }
+    }
+}
+
+fn update_setting_texts(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut setting_texts: Query<(&mut Text, &SettingText), Without<BindingText>>,
+    mut binding_texts: Query<(&mut Text, &BindingText), Without<SettingText>>,
+) {
+    if !settings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (mut text, setting_text) in &mut setting_texts {
+        text.sections[0].value = setting_text.0.text(&settings);
+    }
+    for (mut text, binding) in &mut binding_texts {
+        text.sections[0].value = binding_text(binding.0, &settings, rebinding.0);
+    }
+}
+
+fn save_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    screen: Query<Entity, With<SettingsScreen>>,
+) {
+    settings.save();
+    rebinding.0 = None;
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
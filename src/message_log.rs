@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    color::palettes::css::{CRIMSON, GOLD, LIGHT_GRAY, LIGHT_SKY_BLUE},
+    input::mouse::MouseWheel,
+    prelude::*,
+};
+
+use crate::{
+    movement::{MoveBlocked, MoveBlockedReason},
+    GameState,
+};
+
+pub struct MessageLogPlugin;
+
+/// This plugin collects [`LogMessage`]s, so players can read what happened in the game
+impl Plugin for MessageLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LogMessage>()
+            .init_resource::<MessageLog>()
+            .add_systems(OnEnter(GameState::Menu), clear_message_log)
+            .add_systems(
+                Update,
+                (
+                    log_blocked_moves,
+                    collect_messages,
+                    scroll_message_log.run_if(in_state(GameState::Playing)),
+                    update_message_panels,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCategory {
+    Interaction,
+    Combat,
+    Level,
+    Movement,
+}
+
+impl MessageCategory {
+    fn color(&self) -> Color {
+        match self {
+            MessageCategory::Interaction => GOLD.into(),
+            MessageCategory::Combat => CRIMSON.into(),
+            MessageCategory::Level => LIGHT_SKY_BLUE.into(),
+            MessageCategory::Movement => LIGHT_GRAY.into(),
+        }
+    }
+}
+
+/// Adds a line to the [`MessageLog`]
+#[derive(Debug, Clone, Event)]
+pub struct LogMessage {
+    pub category: MessageCategory,
+    pub text: String,
+}
+
+impl LogMessage {
+    pub fn new(category: MessageCategory, text: impl Into<String>) -> Self {
+        Self {
+            category,
+            text: text.into(),
+        }
+    }
+}
+
+/// The latest messages, older ones are dropped
+#[derive(Debug, Default, Resource)]
+pub struct MessageLog {
+    messages: VecDeque<LogMessage>,
+    /// How many lines the panel is scrolled back from the newest message
+    scroll: usize,
+}
+
+impl MessageLog {
+    const CAPACITY: usize = 200;
+    const VISIBLE_LINES: usize = 6;
+
+    pub fn push(&mut self, message: LogMessage) {
+        if self.messages.len() == Self::CAPACITY {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+
+        /* Keep showing the same lines while scrolled back */
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.max_scroll());
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.messages.len().saturating_sub(Self::VISIBLE_LINES)
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(lines)
+            .min(self.max_scroll());
+    }
+
+    /// The messages in the panel, oldest first
+    fn visible(&self) -> impl Iterator<Item = &LogMessage> {
+        let end = self.messages.len() - self.scroll;
+        let start = end.saturating_sub(Self::VISIBLE_LINES);
+        self.messages.range(start..end)
+    }
+}
+
+#[derive(Component)]
+struct MessagePanel;
+
+/// Spawns the panel showing the latest messages, it fills itself
+pub fn spawn_message_panel(parent: &mut ChildBuilder) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(320.0),
+                min_height: Val::Px(120.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            background_color: Color::srgba(0.05, 0.05, 0.05, 0.7).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((TextBundle::default(), MessagePanel));
+        });
+}
+
+fn clear_message_log(mut message_log: ResMut<MessageLog>) {
+    *message_log = MessageLog::default();
+}
+
+fn log_blocked_moves(
+    mut move_blocked_evr: EventReader<MoveBlocked>,
+    mut log_message_evw: EventWriter<LogMessage>,
+) {
+    for event in move_blocked_evr.read() {
+        let text = match event.reason {
+            MoveBlockedReason::Boundary | MoveBlockedReason::Void => "A wall blocks the way",
+            MoveBlockedReason::Pit => "A pit blocks the way",
+            MoveBlockedReason::Interactable => "Something blocks the way",
+        };
+        log_message_evw.send(LogMessage::new(MessageCategory::Movement, text));
+    }
+}
+
+fn collect_messages(
+    mut log_message_evr: EventReader<LogMessage>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for message in log_message_evr.read() {
+        info!("{:?}: {}", message.category, message.text);
+        message_log.push(message.clone());
+    }
+}
+
+/// Page up and down or the mouse wheel scroll through the history
+fn scroll_message_log(
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel_evr: EventReader<MouseWheel>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    let mut lines = 0;
+    if key_input.just_pressed(KeyCode::PageUp) {
+        lines += MessageLog::VISIBLE_LINES as isize;
+    }
+    if key_input.just_pressed(KeyCode::PageDown) {
+        lines -= MessageLog::VISIBLE_LINES as isize;
+    }
+    for event in mouse_wheel_evr.read() {
+        lines += event.y.signum() as isize;
+    }
+
+    if lines != 0 {
+        message_log.scroll_by(lines);
+    }
+}
+
+fn update_message_panels(
+    message_log: Res<MessageLog>,
+    mut panels: Query<(Ref<MessagePanel>, &mut Text)>,
+) {
+    for (panel, mut text) in &mut panels {
+        if !message_log.is_changed() && !panel.is_added() {
+            continue;
+        }
+
+        text.sections = message_log
+            .visible()
+            .enumerate()
+            .map(|(index, message)| {
+                let separator = if index == 0 { "" } else { "\n" };
+                TextSection::new(
+                    format!("{separator}{}", message.text),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: message.category.color(),
+                        ..default()
+                    },
+                )
+            })
+            .collect();
+    }
+}
@@ -1,11 +1,12 @@
 use std::{num::TryFromIntError, time::Duration};
 
 use bevy::prelude::*;
-use bevy_easings::{Ease, EaseFunction, EasingComponent, EasingType};
-use serde::Deserialize;
+use bevy_easings::{Ease, EaseFunction, EasingChainComponent, EasingComponent, EasingType};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    level::{CurrentLevel, Level, Tile, TILE_SIZE},
+    level::{CurrentLevel, Level, LevelState, LevelStates, Tile, TILE_SIZE},
+    settings::{Easing, Settings},
     GameState,
 };
 
@@ -15,9 +16,21 @@ impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FaceDirection>()
             .add_event::<MoveForward>()
+            .add_event::<TurnTaken>()
+            .add_event::<MoveBlocked>()
+            .add_event::<TileEntered>()
+            .add_event::<Teleport>()
+            .init_resource::<EaseSettings>()
+            .add_systems(Update, update_ease_settings)
             .add_systems(
                 Update,
-                (move_forward, face_direction).run_if(in_state(GameState::Playing)),
+                (
+                    move_forward,
+                    face_direction,
+                    bump.after(move_forward),
+                    teleport,
+                )
+                    .run_if(in_state(GameState::Playing)),
             )
             .add_systems(
                 Update,
@@ -40,7 +53,7 @@ impl Plugin for MovementPlugin {
     }
 }
 
-#[derive(Default, Debug, Component, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Default, Debug, Component, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct GridPosition {
     pub x: usize,
     pub y: usize,
@@ -70,9 +83,13 @@ impl GridPosition {
             y: usize::try_from(self.y as i32 + delta.1)?,
         })
     }
+
+    pub fn manhattan_distance(&self, other: &GridPosition) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
 }
 
-#[derive(Default, Debug, Component, Clone, Copy)]
+#[derive(Default, Debug, Component, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum GridDirection {
     North,
     East,
@@ -116,6 +133,13 @@ impl From<GridDirection> for Dir3 {
 }
 
 impl GridDirection {
+    pub const ALL: [GridDirection; 4] = [
+        GridDirection::North,
+        GridDirection::East,
+        GridDirection::South,
+        GridDirection::West,
+    ];
+
     pub fn front(&self) -> Self {
         *self
     }
@@ -159,6 +183,87 @@ pub struct MoveForward {
     pub entity: Entity,
 }
 
+/// Sent when [`MoveForward`] was rejected, e.g. to play a bump sound
+#[derive(Debug, Event)]
+pub struct MoveBlocked {
+    pub entity: Entity,
+    pub reason: MoveBlockedReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveBlockedReason {
+    /// The next cell is outside of the level
+    Boundary,
+    Void,
+    /// The next cell is a pit without an extended bridge or a collapsed floor
+    Pit,
+    Interactable,
+}
+
+/// Puts the entity on the cell instantly, without checking if it can stand there
+#[derive(Debug, Event)]
+pub struct Teleport {
+    pub entity: Entity,
+    pub position: GridPosition,
+}
+
+/// Sent whenever an entity arrived at a cell, by moving or being teleported
+#[derive(Debug, Event)]
+pub struct TileEntered {
+    pub entity: Entity,
+    pub position: GridPosition,
+}
+
+/// Sent whenever an entity finished moving or turning
+#[derive(Debug, Event)]
+pub struct TurnTaken {
+    pub entity: Entity,
+}
+
+/// How long moves and turns take and how they are eased
+///
+/// As a component it overrides the resource, which follows the [`Settings`] of the player
+#[derive(Debug, Clone, Copy, Component, Resource)]
+pub struct EaseSettings {
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Default for EaseSettings {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(300),
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl From<&Settings> for EaseSettings {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            duration: settings.move_duration(),
+            easing: settings.easing,
+        }
+    }
+}
+
+impl EaseSettings {
+    /// A zero duration finishes moves and turns instantly
+    pub fn is_snap(&self) -> bool {
+        self.duration.is_zero()
+    }
+
+    fn ease_to(&self, transform: &Transform, target: Transform) -> EasingComponent<Transform> {
+        transform.ease_to(
+            target,
+            self.easing.method(),
+            EasingType::Once {
+                duration: self.duration,
+            },
+        )
+    }
+}
+
 #[derive(Debug, Component)]
 pub struct EaseTo<T: Copy> {
     pub target: T,
@@ -170,67 +275,119 @@ impl<T: Copy> EaseTo<T> {
     }
 }
 
+/// Components on the grid that are eased to, see [`EaseTo`]
+pub trait GridEase: Component + Copy {
+    /// The cell that is entered by arriving at the target, turns stay on their cell
+    fn entered_tile(&self) -> Option<GridPosition> {
+        None
+    }
+}
+
+impl GridEase for GridPosition {
+    fn entered_tile(&self) -> Option<GridPosition> {
+        Some(*self)
+    }
+}
+
+impl GridEase for GridDirection {}
+
+fn update_ease_settings(settings: Res<Settings>, mut ease_settings: ResMut<EaseSettings>) {
+    if settings.is_changed() {
+        *ease_settings = EaseSettings::from(settings.as_ref());
+    }
+}
+
+/* Snapping entities get no easing, end_of_ease picks them up directly */
 fn ease_grid_position_to_translation(
     mut commands: Commands,
-    query: Query<(Entity, &Transform, &EaseTo<GridPosition>), Added<EaseTo<GridPosition>>>,
+    default_ease_settings: Res<EaseSettings>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &EaseTo<GridPosition>,
+            Option<&EaseSettings>,
+        ),
+        Added<EaseTo<GridPosition>>,
+    >,
 ) {
-    for (entity, transform, grid_position) in &query {
+    for (entity, transform, grid_position, ease_settings) in &query {
+        let ease_settings = ease_settings.unwrap_or(&default_ease_settings);
+        if ease_settings.is_snap() {
+            continue;
+        }
+
         let new_grid_position = &grid_position.target;
         let new_transform = transform.with_translation(new_grid_position.into());
 
-        commands.entity(entity).insert(transform.ease_to(
-            new_transform,
-            EaseFunction::SineInOut,
-            EasingType::Once {
-                duration: Duration::from_millis(300),
-            },
-        ));
+        commands
+            .entity(entity)
+            .insert(ease_settings.ease_to(transform, new_transform));
     }
 }
 
 fn ease_direction_to_rotation(
     mut commands: Commands,
-    query: Query<(Entity, &Transform, &EaseTo<GridDirection>), Added<EaseTo<GridDirection>>>,
+    default_ease_settings: Res<EaseSettings>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &EaseTo<GridDirection>,
+            Option<&EaseSettings>,
+        ),
+        Added<EaseTo<GridDirection>>,
+    >,
 ) {
-    for (entity, transform, direction) in &query {
+    for (entity, transform, direction, ease_settings) in &query {
+        let ease_settings = ease_settings.unwrap_or(&default_ease_settings);
+        if ease_settings.is_snap() {
+            continue;
+        }
+
         let new_direction = &direction.target;
         let new_transform = transform.looking_to(new_direction, Vec3::Y);
 
-        commands.entity(entity).insert(transform.ease_to(
-            new_transform,
-            EaseFunction::SineInOut,
-            EasingType::Once {
-                duration: Duration::from_millis(300),
-            },
-        ));
+        commands
+            .entity(entity)
+            .insert(ease_settings.ease_to(transform, new_transform));
     }
 }
 
-fn move_forward(
+/// How far the bump animation moves into the blocked cell
+const BUMP_DISTANCE: f32 = TILE_SIZE * 0.15;
+
+pub fn move_forward(
     mut commands: Commands,
     mut move_forward_evr: EventReader<MoveForward>,
-    query: Query<(&GridPosition, &GridDirection), Without<EasingComponent<Transform>>>,
+    query: Query<
+        (&GridPosition, &GridDirection),
+        (
+            Without<EasingComponent<Transform>>,
+            Without<EasingChainComponent<Transform>>,
+        ),
+    >,
     current_level: Res<CurrentLevel>,
     level_assets: Res<Assets<Level>>,
+    level_states: Res<LevelStates>,
+    mut move_blocked_evw: EventWriter<MoveBlocked>,
 ) {
     /* TODO: Other stuff occupying the position need to be checked */
     if let Some(level) = level_assets.get(&current_level.0) {
+        let level_state = level_states.get(&current_level.0);
         for event in move_forward_evr.read() {
             if let Ok((grid_position, direction)) = query.get(event.entity) {
-                if let Ok(next_position) = grid_position.next(direction) {
-                    /* Check the outer boundaries */
-                    if next_position.y < level.grid.len()
-                        && next_position.x < level.grid[next_position.y].len()
-                    {
-                        /* Check for void */
-                        if !matches!(level.grid[next_position.y][next_position.x], Tile::Void) {
-                            /* Check for Interactables */
-                            if !level.interactables.contains_key(&next_position) {
-                                commands
-                                    .entity(event.entity)
-                                    .insert(EaseTo::new(next_position));
-                            }
-                        }
+                match check_move(level, level_state, grid_position, direction) {
+                    Ok(next_position) => {
+                        commands
+                            .entity(event.entity)
+                            .insert(EaseTo::new(next_position));
+                    }
+                    Err(reason) => {
+                        move_blocked_evw.send(MoveBlocked {
+                            entity: event.entity,
+                            reason,
+                        });
                     }
                 }
             }
@@ -238,10 +395,81 @@ fn move_forward(
     }
 }
 
-fn face_direction(
+fn check_move(
+    level: &Level,
+    level_state: Option<&LevelState>,
+    grid_position: &GridPosition,
+    direction: &GridDirection,
+) -> Result<GridPosition, MoveBlockedReason> {
+    let next_position = grid_position
+        .next(direction)
+        .map_err(|_| MoveBlockedReason::Boundary)?;
+
+    /* Check the outer boundaries */
+    if next_position.y >= level.grid.len() || next_position.x >= level.grid[next_position.y].len() {
+        return Err(MoveBlockedReason::Boundary);
+    }
+    /* Check for void */
+    if matches!(level.grid[next_position.y][next_position.x], Tile::Void) {
+        return Err(MoveBlockedReason::Void);
+    }
+    /* Check for pits and Interactables, both depend on the state of mechanisms */
+    if !level.is_walkable(&next_position, level_state) {
+        return match level.grid[next_position.y][next_position.x] {
+            Tile::Pit => Err(MoveBlockedReason::Pit),
+            _ if level.is_collapsed(&next_position, level_state) => Err(MoveBlockedReason::Pit),
+            _ => Err(MoveBlockedReason::Interactable),
+        };
+    }
+
+    Ok(next_position)
+}
+
+/// Nudges blocked entities towards the blocked cell and back
+fn bump(
+    mut commands: Commands,
+    mut move_blocked_evr: EventReader<MoveBlocked>,
+    default_ease_settings: Res<EaseSettings>,
+    query: Query<(&Transform, &GridDirection, Option<&EaseSettings>)>,
+) {
+    for event in move_blocked_evr.read() {
+        let Ok((transform, direction, ease_settings)) = query.get(event.entity) else {
+            continue;
+        };
+        let ease_settings = ease_settings.unwrap_or(&default_ease_settings);
+        if ease_settings.is_snap() {
+            continue;
+        }
+
+        let half = ease_settings.duration / 2;
+        let bumped = transform
+            .with_translation(transform.translation + Vec3::from(direction) * BUMP_DISTANCE);
+        commands.entity(event.entity).insert(
+            transform
+                .ease_to(
+                    bumped,
+                    EaseFunction::QuadraticOut,
+                    EasingType::Once { duration: half },
+                )
+                .ease_to(
+                    *transform,
+                    EaseFunction::QuadraticIn,
+                    EasingType::Once { duration: half },
+                ),
+        );
+    }
+}
+
+pub fn face_direction(
     mut commands: Commands,
     mut face_direction_evr: EventReader<FaceDirection>,
-    directions: Query<&GridDirection, Without<EasingComponent<Transform>>>,
+    directions: Query<
+        &GridDirection,
+        (
+            Without<EasingComponent<Transform>>,
+            Without<EasingChainComponent<Transform>>,
+        ),
+    >,
 ) {
     for event in face_direction_evr.read() {
         if let Ok(_direction) = directions.get(event.entity) {
@@ -252,6 +480,22 @@ fn face_direction(
     }
 }
 
+fn teleport(
+    mut teleport_evr: EventReader<Teleport>,
+    mut positions: Query<&mut GridPosition>,
+    mut tile_entered_evw: EventWriter<TileEntered>,
+) {
+    for event in teleport_evr.read() {
+        if let Ok(mut position) = positions.get_mut(event.entity) {
+            *position = event.position;
+            tile_entered_evw.send(TileEntered {
+                entity: event.entity,
+                position: event.position,
+            });
+        }
+    }
+}
+
 fn grid_position_to_translation(
     mut query: Query<(&mut Transform, &GridPosition), Changed<GridPosition>>,
 ) {
@@ -268,16 +512,29 @@ fn direction_to_rotation(
     }
 }
 
-fn end_of_ease<T: Component + Copy>(
+fn end_of_ease<T: GridEase>(
     mut commands: Commands,
+    default_ease_settings: Res<EaseSettings>,
     mut removed: RemovedComponents<EasingComponent<Transform>>,
+    started: Query<(Entity, Option<&EaseSettings>), Added<EaseTo<T>>>,
     mut query: Query<(&mut T, &EaseTo<T>)>,
+    mut turn_taken_evw: EventWriter<TurnTaken>,
+    mut tile_entered_evw: EventWriter<TileEntered>,
 ) {
-    for entity in removed.read() {
+    let snapped = started
+        .iter()
+        .filter(|(_, ease_settings)| ease_settings.unwrap_or(&default_ease_settings).is_snap())
+        .map(|(entity, _)| entity);
+
+    for entity in removed.read().chain(snapped) {
         if let Ok((mut component, ease_to)) = query.get_mut(entity) {
             *component = ease_to.target;
 
             commands.entity(entity).remove::<EaseTo<T>>();
+            turn_taken_evw.send(TurnTaken { entity });
+            if let Some(position) = ease_to.target.entered_tile() {
+                tile_entered_evw.send(TileEntered { entity, position });
+            }
         }
     }
 }
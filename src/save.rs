@@ -0,0 +1,118 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    flags::GameFlags,
+    health::Health,
+    inventory::Inventory,
+    level::{CurrentLevel, LevelState, LevelStates, LightFuel, Player},
+    loading::LevelAssets,
+    loot::LootSeed,
+    movement::{GridDirection, GridPosition},
+    settings::{load_config, save_config},
+    toast::Toast,
+};
+
+pub struct SavePlugin;
+
+/// This plugin writes the running game to the config directory on [`SaveGame`]
+///
+/// A [`SavedGame`] resource makes the next game continue from it instead of starting anew
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGame>().add_systems(Update, save_game);
+    }
+}
+
+/// Stores the running game, replacing the previous save
+#[derive(Debug, Event)]
+pub struct SaveGame;
+
+/// Everything needed to continue a game, kept next to the settings
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// Path of the level the player is in, e.g. `level/000.lvl`
+    pub level: String,
+    pub position: GridPosition,
+    pub direction: GridDirection,
+    pub fuel: LightFuel,
+    /// Defaults to full health
+    #[serde(default)]
+    pub health: Option<Health>,
+    #[serde(default)]
+    pub inventory: Inventory,
+    #[serde(default)]
+    pub flags: GameFlags,
+    #[serde(default)]
+    pub loot_seed: LootSeed,
+    /// What happened in the visited levels, by the path of the level
+    #[serde(default)]
+    pub level_states: HashMap<String, LevelState>,
+}
+
+impl SavedGame {
+    const FILE_NAME: &'static str = "save.ron";
+
+    /// `None` if nothing was saved yet or the save is broken
+    pub fn load() -> Option<Self> {
+        load_config(Self::FILE_NAME)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        save_config(Self::FILE_NAME, self)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    mut save_game_evr: EventReader<SaveGame>,
+    current_level: Res<CurrentLevel>,
+    players: Query<
+        (
+            &GridPosition,
+            &GridDirection,
+            &LightFuel,
+            &Health,
+            &Inventory,
+        ),
+        With<Player>,
+    >,
+    flags: Res<GameFlags>,
+    loot_seed: Res<LootSeed>,
+    level_states: Res<LevelStates>,
+    level_assets: Res<LevelAssets>,
+    mut toast_evw: EventWriter<Toast>,
+) {
+    if save_game_evr.read().count() == 0 {
+        return;
+    }
+
+    let (Some(level), Ok((position, direction, fuel, health, inventory))) =
+        (current_level.path(), players.get_single())
+    else {
+        toast_evw.send(Toast("There is no game to save".to_string()));
+        return;
+    };
+
+    let saved_game = SavedGame {
+        level: level.to_string(),
+        position: *position,
+        direction: *direction,
+        fuel: *fuel,
+        health: Some(*health),
+        inventory: inventory.clone(),
+        flags: flags.clone(),
+        loot_seed: *loot_seed,
+        level_states: level_states.by_path(&level_assets.levels),
+    };
+    match saved_game.save() {
+        Ok(()) => {
+            info!("Saved the game in {}", saved_game.level);
+            toast_evw.send(Toast("Game saved".to_string()));
+        }
+        Err(error) => {
+            warn!("Could not save the game: {error}");
+            toast_evw.send(Toast(format!("Could not save the game: {error}")));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowMode},
+};
+use bevy_easings::{EaseFunction, EaseMethod};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub struct SettingsPlugin;
+
+/// This plugin loads the [`Settings`] from the config directory of the user and applies them
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_systems(Update, (apply_settings, apply_field_of_view));
+    }
+}
+
+/// Preferences of the player, persisted between sessions
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Duration of a single move or turn in milliseconds, 0 snaps instantly
+    pub move_duration: u64,
+    pub easing: Easing,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    /// Vertical field of view of the camera in degrees
+    pub field_of_view: f32,
+    /// Head-bob, lean and screen shake, can be turned off for motion-sensitive players
+    pub camera_effects: bool,
+    pub window_mode: WindowMode,
+    pub key_bindings: KeyBindings,
+    /// Language of the texts in levels that come from a text table
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            move_duration: 300,
+            easing: Easing::default(),
+            master_volume: 1.0,
+            music_volume: 0.8,
+            effects_volume: 1.0,
+            field_of_view: 45.0,
+            camera_effects: true,
+            window_mode: WindowMode::Windowed,
+            key_bindings: KeyBindings::default(),
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    const FILE_NAME: &'static str = "settings.ron";
+
+    pub fn move_duration(&self) -> Duration {
+        Duration::from_millis(self.move_duration)
+    }
+
+    /// Falls back to the default settings, if there are none or they are broken
+    pub fn load() -> Self {
+        load_config(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) {
+        if let Err(error) = save_config(Self::FILE_NAME, self) {
+            warn!("Could not save settings: {error}");
+        }
+    }
+
+    /* There is no config directory on the web */
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) {}
+}
+
+/// Path of a file in the config directory of the game, e.g. of `settings.ron`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_path(file_name: &str) -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("bevy_game_dungeon").join(file_name))
+}
+
+/// Reads a RON file from the config directory, `None` if there is none or it is broken
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_config<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let path = config_path(file_name)?;
+    let content = std::fs::read_to_string(&path).ok()?;
+    ron::from_str(&content)
+        .map_err(|error| warn!("Could not parse {}: {error}", path.display()))
+        .ok()
+}
+
+/// Writes a RON file to the config directory, creating the directory if needed
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_config<T: Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let Some(path) = config_path(file_name) else {
+        return Err("There is no config directory".to_string());
+    };
+
+    let content = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(&path, content).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load_config<T: DeserializeOwned>(_file_name: &str) -> Option<T> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_config<T: Serialize>(_file_name: &str, _value: &T) -> Result<(), String> {
+    Err("Saving is not supported on the web".to_string())
+}
+
+/// The languages there are text tables for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Name of the text table, e.g. `text/en.texts`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+}
+
+/// The easing functions that can be chosen for movement animations
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    QuadraticInOut,
+    CubicInOut,
+    #[default]
+    SineInOut,
+    BackInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 5] = [
+        Easing::Linear,
+        Easing::QuadraticInOut,
+        Easing::CubicInOut,
+        Easing::SineInOut,
+        Easing::BackInOut,
+    ];
+
+    pub fn method(&self) -> EaseMethod {
+        match self {
+            Easing::Linear => EaseMethod::Linear,
+            Easing::QuadraticInOut => EaseFunction::QuadraticInOut.into(),
+            Easing::CubicInOut => EaseFunction::CubicInOut.into(),
+            Easing::SineInOut => EaseFunction::SineInOut.into(),
+            Easing::BackInOut => EaseFunction::BackInOut.into(),
+        }
+    }
+}
+
+/// Everything the player can do with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveForward,
+    TurnLeft,
+    TurnRight,
+    TurnAround,
+    Interact,
+    AutoExplore,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::MoveForward,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::TurnAround,
+        Action::Interact,
+        Action::AutoExplore,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "Move forward",
+            Action::TurnLeft => "Turn left",
+            Action::TurnRight => "Turn right",
+            Action::TurnAround => "Turn around",
+            Action::Interact => "Interact",
+            Action::AutoExplore => "Auto explore",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub move_forward: KeyCode,
+    pub turn_left: KeyCode,
+    pub turn_right: KeyCode,
+    pub turn_around: KeyCode,
+    pub interact: KeyCode,
+    pub auto_explore: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            move_forward: KeyCode::ArrowUp,
+            turn_left: KeyCode::ArrowLeft,
+            turn_right: KeyCode::ArrowRight,
+            turn_around: KeyCode::ArrowDown,
+            interact: KeyCode::Space,
+            auto_explore: KeyCode::KeyX,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::MoveForward => self.move_forward,
+            Action::TurnLeft => self.turn_left,
+            Action::TurnRight => self.turn_right,
+            Action::TurnAround => self.turn_around,
+            Action::Interact => self.interact,
+            Action::AutoExplore => self.auto_explore,
+        }
+    }
+
+    pub fn bind(&mut self, action: Action, key_code: KeyCode) {
+        let key = match action {
+            Action::MoveForward => &mut self.move_forward,
+            Action::TurnLeft => &mut self.turn_left,
+            Action::TurnRight => &mut self.turn_right,
+            Action::TurnAround => &mut self.turn_around,
+            Action::Interact => &mut self.interact,
+            Action::AutoExplore => &mut self.auto_explore,
+        };
+        *key = key_code;
+    }
+}
+
+fn apply_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    commands.insert_resource(GlobalVolume::new(settings.master_volume));
+    for mut window in &mut windows {
+        window.mode = settings.window_mode;
+    }
+}
+
+/// Also applied to cameras that were just spawned
+fn apply_field_of_view(
+    settings: Res<Settings>,
+    mut projections: Query<&mut Projection, With<Camera3d>>,
+) {
+    for mut projection in &mut projections {
+        if !settings.is_changed() && !projection.is_added() {
+            continue;
+        }
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = settings.field_of_view.to_radians();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::{color::palettes::css::ORANGE_RED, prelude::*};
+
+pub struct ToastPlugin;
+
+/// This plugin shows short lived messages on screen, e.g. for errors that should not crash the game
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .add_systems(Startup, setup_toasts)
+            .add_systems(Update, (show_toasts, expire_toasts));
+    }
+}
+
+#[derive(Debug, Event)]
+pub struct Toast(pub String);
+
+/// Container all toasts are stacked in
+#[derive(Component)]
+struct Toasts;
+
+#[derive(Component)]
+struct ToastTimer(Timer);
+
+fn setup_toasts(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(5.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        Toasts,
+    ));
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut toast_evr: EventReader<Toast>,
+    toasts: Query<Entity, With<Toasts>>,
+) {
+    let Ok(toasts) = toasts.get_single() else {
+        return;
+    };
+
+    for toast in toast_evr.read() {
+        commands.entity(toasts).with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::srgba(0.1, 0.1, 0.1, 0.9).into(),
+                        ..default()
+                    },
+                    ToastTimer(Timer::from_seconds(5.0, TimerMode::Once)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        toast.0.clone(),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: ORANGE_RED.into(),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timers: Query<(Entity, &mut ToastTimer)>,
+) {
+    for (entity, mut timer) in &mut timers {
+        if timer.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
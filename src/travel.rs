@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_easings::{EasingChainComponent, EasingComponent};
+
+use crate::{
+    level::{CurrentLevel, Enemy, Level, LevelStates, PathStep, Pathfinder, Player, Viewshed},
+    movement::{
+        face_direction, move_forward, EaseTo, FaceDirection, GridDirection, GridPosition,
+        MoveBlocked, MoveForward,
+    },
+    GameState,
+};
+
+pub struct TravelPlugin;
+
+impl Plugin for TravelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TravelTo>()
+            .add_event::<AutoExplore>()
+            .add_systems(
+                Update,
+                (
+                    plan_travel,
+                    abort_travel_on_move_blocked,
+                    abort_travel_on_enemy_sight,
+                    follow_travel,
+                )
+                    .chain()
+                    .before(move_forward)
+                    .before(face_direction)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Walk `entity` to `target`, e.g. when a cell of the floor was clicked
+#[derive(Debug, Event)]
+pub struct TravelTo {
+    pub entity: Entity,
+    pub target: GridPosition,
+}
+
+/// Walk `entity` to the nearest reachable cell that was not explored yet
+#[derive(Debug, Event)]
+pub struct AutoExplore {
+    pub entity: Entity,
+}
+
+/// The remaining steps of a travel, which are turned into [`FaceDirection`] and [`MoveForward`] events
+#[derive(Debug, Component)]
+pub struct Travel {
+    steps: VecDeque<PathStep>,
+    last_position: Option<GridPosition>,
+}
+
+impl Travel {
+    fn new(steps: Vec<PathStep>) -> Self {
+        Self {
+            steps: steps.into(),
+            last_position: None,
+        }
+    }
+}
+
+fn plan_travel(
+    mut commands: Commands,
+    mut travel_to_evr: EventReader<TravelTo>,
+    mut auto_explore_evr: EventReader<AutoExplore>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    level_states: Res<LevelStates>,
+    positions: Query<(Entity, &GridPosition)>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    let level_state = level_states.get(&current_level.0);
+    let pathfinder_for = |entity: Entity| {
+        Pathfinder::new(level)
+            .with_level_state(level_state)
+            .with_occupied(
+                positions
+                    .iter()
+                    .filter(|(other, _)| *other != entity)
+                    .map(|(_, grid_position)| *grid_position),
+            )
+    };
+
+    for event in travel_to_evr.read() {
+        let Ok((_, grid_position)) = positions.get(event.entity) else {
+            continue;
+        };
+
+        match pathfinder_for(event.entity).find_path(*grid_position, event.target) {
+            Some(steps) => {
+                commands.entity(event.entity).insert(Travel::new(steps));
+            }
+            None => info!("No path to {:?}", event.target),
+        }
+    }
+
+    for event in auto_explore_evr.read() {
+        let Ok((_, grid_position)) = positions.get(event.entity) else {
+            continue;
+        };
+
+        let pathfinder = pathfinder_for(event.entity);
+        let explored = level_states
+            .get(&current_level.0)
+            .map(|level_state| &level_state.explored);
+
+        /* Ties are broken by position, so exploring is deterministic */
+        let target = pathfinder
+            .distance_map(*grid_position)
+            .into_iter()
+            .filter(|(position, _)| !explored.is_some_and(|explored| explored.contains(position)))
+            .min_by_key(|(position, distance)| (*distance, position.y, position.x))
+            .map(|(position, _)| position);
+
+        match target.and_then(|target| pathfinder.find_path(*grid_position, target)) {
+            Some(steps) => {
+                commands.entity(event.entity).insert(Travel::new(steps));
+            }
+            None => info!("Nothing left to explore"),
+        }
+    }
+}
+
+/* The level changed under the path, e.g. a door closed */
+fn abort_travel_on_move_blocked(
+    mut commands: Commands,
+    mut move_blocked_evr: EventReader<MoveBlocked>,
+    travellers: Query<Entity, With<Travel>>,
+) {
+    for event in move_blocked_evr.read() {
+        if travellers.contains(event.entity) {
+            info!("Path is blocked by {:?}, stop travelling", event.reason);
+            commands.entity(event.entity).remove::<Travel>();
+        }
+    }
+}
+
+fn abort_travel_on_enemy_sight(
+    mut commands: Commands,
+    travellers: Query<(Entity, &Viewshed), (With<Travel>, With<Player>)>,
+    enemies: Query<&GridPosition, With<Enemy>>,
+) {
+    for (entity, viewshed) in &travellers {
+        if enemies
+            .iter()
+            .any(|grid_position| viewshed.visible.contains(grid_position))
+        {
+            info!("Enemy in sight, stop travelling");
+            commands.entity(entity).remove::<Travel>();
+        }
+    }
+}
+
+/// Interactables on the cells around `grid_position`
+fn adjacent_interactables(level: &Level, grid_position: &GridPosition) -> HashSet<GridPosition> {
+    GridDirection::ALL
+        .iter()
+        .filter_map(|direction| grid_position.next(direction).ok())
+        .filter(|position| level.interactables.contains_key(position))
+        .collect()
+}
+
+fn follow_travel(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<Level>>,
+    mut travellers: Query<
+        (Entity, &GridPosition, &GridDirection, &mut Travel),
+        (
+            Without<EasingComponent<Transform>>,
+            Without<EasingChainComponent<Transform>>,
+            Without<EaseTo<GridPosition>>,
+            Without<EaseTo<GridDirection>>,
+        ),
+    >,
+    mut face_direction_evw: EventWriter<FaceDirection>,
+    mut move_forward_evw: EventWriter<MoveForward>,
+) {
+    let Some(level) = level_assets.get(&current_level.0) else {
+        return;
+    };
+
+    for (entity, grid_position, direction, mut travel) in &mut travellers {
+        /* Stop next to interactables that were not next to the previous cell */
+        if let Some(last_position) = travel.last_position {
+            if last_position != *grid_position {
+                let known = adjacent_interactables(level, &last_position);
+                if !adjacent_interactables(level, grid_position).is_subset(&known) {
+                    info!("Reached an interactable, stop travelling");
+                    commands.entity(entity).remove::<Travel>();
+                    continue;
+                }
+            }
+        }
+        travel.last_position = Some(*grid_position);
+
+        while travel
+            .steps
+            .front()
+            .is_some_and(|step| step.position == *grid_position)
+        {
+            travel.steps.pop_front();
+        }
+
+        let Some(step) = travel.steps.front() else {
+            commands.entity(entity).remove::<Travel>();
+            continue;
+        };
+
+        if *direction != step.direction {
+            face_direction_evw.send(FaceDirection {
+                entity,
+                direction: step.direction,
+            });
+        } else if grid_position.next(direction).ok() == Some(step.position) {
+            move_forward_evw.send(MoveForward { entity });
+        } else {
+            /* Moved off the path somehow */
+            commands.entity(entity).remove::<Travel>();
+        }
+    }
+}